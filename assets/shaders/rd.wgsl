@@ -20,6 +20,39 @@ var<uniform> uniform_db: f32;
 var<uniform> uniform_feed: f32;
 @group(1) @binding(5)
 var<uniform> uniform_kill: f32;
+@group(1) @binding(6)
+var<uniform> uniform_resolution: vec2<f32>;
+@group(1) @binding(7)
+var<uniform> uniform_map_mode: u32;
+@group(1) @binding(8)
+var<uniform> uniform_map_strength: vec4<f32>;
+@group(1) @binding(9)
+var map_tex: texture_2d<f32>;
+@group(1) @binding(10)
+var map_samp: sampler;
+@group(1) @binding(11)
+var<uniform> uniform_action: u32;
+// x: random seed, y: noise density
+@group(1) @binding(12)
+var<uniform> uniform_action_params: vec4<f32>;
+// RDModel in src/rd/models.rs
+@group(1) @binding(13)
+var<uniform> uniform_model: u32;
+// Du, Dv, epsilon, a0
+@group(1) @binding(14)
+var<uniform> uniform_fhn: vec4<f32>;
+// a, b, Dx, Dy
+@group(1) @binding(15)
+var<uniform> uniform_brusselator: vec4<f32>;
+// species, threshold, range, noise
+@group(1) @binding(16)
+var<uniform> uniform_cyclic: vec4<f32>;
+// radius, mu, sigma, dt
+@group(1) @binding(17)
+var<uniform> uniform_lenia: vec4<f32>;
+// 25 kernel weights from RDKernel in src/rd/kernel.rs, row by row
+@group(1) @binding(18)
+var<uniform> uniform_laplace: array<vec4<f32>, 7>;
 
 fn rot3(axis: vec3<f32>, angle: f32) -> mat3x3<f32> {
     let an = normalize(axis);
@@ -46,50 +79,47 @@ fn rgb2hsv(c: vec3<f32>) -> vec3<f32> {
     return vec3<f32>(abs(q.z + (q.w - q.y) / (6.0 * d + e)), d / (q.x + e), q.x);
 }
 
+// Weighted sum of the 5x5 taps in uniform_laplace, the center one being the cell itself
 fn laplace(uv: vec2<f32>) -> vec4<f32>
 {
-    var out_val = textureSample(prev_tex, prev_samp, uv) * 9.;
-	var resolution = 1024.0;
-	out_val -= textureSample(prev_tex, prev_samp, uv + vec2<f32>(-2.0/resolution, -2.0/resolution)) * 0.04;
-	out_val -= textureSample(prev_tex, prev_samp, uv + vec2<f32>(-2.0/resolution, -1.0/resolution)) * 0.16;
-	out_val -= textureSample(prev_tex, prev_samp, uv + vec2<f32>(-2.0/resolution, 0.0/resolution)) * 0.20;
-	out_val -= textureSample(prev_tex, prev_samp, uv + vec2<f32>(-2.0/resolution, 1.0/resolution)) * 0.16;
-	out_val -= textureSample(prev_tex, prev_samp, uv + vec2<f32>(-2.0/resolution, 2.0/resolution)) * 0.04;
-	out_val -= textureSample(prev_tex, prev_samp, uv + vec2<f32>(-1.0/resolution, -2.0/resolution)) * 0.16;
-	out_val -= textureSample(prev_tex, prev_samp, uv + vec2<f32>(-1.0/resolution, -1.0/resolution)) * 0.64;
-	out_val -= textureSample(prev_tex, prev_samp, uv + vec2<f32>(-1.0/resolution, 0.0/resolution)) * 0.80;
-	out_val -= textureSample(prev_tex, prev_samp, uv + vec2<f32>(-1.0/resolution, 1.0/resolution)) * 0.64;
-	out_val -= textureSample(prev_tex, prev_samp, uv + vec2<f32>(-1.0/resolution, 2.0/resolution)) * 0.16;
-	out_val -= textureSample(prev_tex, prev_samp, uv + vec2<f32>(0.0/resolution, -2.0/resolution)) * 0.20;
-	out_val -= textureSample(prev_tex, prev_samp, uv + vec2<f32>(0.0/resolution, -1.0/resolution)) * 0.80;
-	out_val -= textureSample(prev_tex, prev_samp, uv + vec2<f32>(0.0/resolution, 0.0/resolution)) * 1.00;
-	out_val -= textureSample(prev_tex, prev_samp, uv + vec2<f32>(0.0/resolution, 1.0/resolution)) * 0.80;
-	out_val -= textureSample(prev_tex, prev_samp, uv + vec2<f32>(0.0/resolution, 2.0/resolution)) * 0.20;
-	out_val -= textureSample(prev_tex, prev_samp, uv + vec2<f32>(1.0/resolution, -2.0/resolution)) * 0.16;
-	out_val -= textureSample(prev_tex, prev_samp, uv + vec2<f32>(1.0/resolution, -1.0/resolution)) * 0.64;
-	out_val -= textureSample(prev_tex, prev_samp, uv + vec2<f32>(1.0/resolution, 0.0/resolution)) * 0.80;
-	out_val -= textureSample(prev_tex, prev_samp, uv + vec2<f32>(1.0/resolution, 1.0/resolution)) * 0.64;
-	out_val -= textureSample(prev_tex, prev_samp, uv + vec2<f32>(1.0/resolution, 2.0/resolution)) * 0.16;
-	out_val -= textureSample(prev_tex, prev_samp, uv + vec2<f32>(2.0/resolution, -2.0/resolution)) * 0.04;
-	out_val -= textureSample(prev_tex, prev_samp, uv + vec2<f32>(2.0/resolution, -1.0/resolution)) * 0.16;
-	out_val -= textureSample(prev_tex, prev_samp, uv + vec2<f32>(2.0/resolution, 0.0/resolution)) * 0.20;
-	out_val -= textureSample(prev_tex, prev_samp, uv + vec2<f32>(2.0/resolution, 1.0/resolution)) * 0.16;
-	out_val -= textureSample(prev_tex, prev_samp, uv + vec2<f32>(2.0/resolution, 2.0/resolution)) * 0.04;
-
-
-    return out_val * 0.25;
+    let px = 1.0 / uniform_resolution;
+    var out_val = vec4<f32>(0.);
+    for (var i = 0; i < 25; i = i + 1) {
+        let offset = vec2<f32>(f32(i % 5) - 2., f32(i / 5) - 2.);
+        out_val += textureSample(prev_tex, prev_samp, uv + offset * px) * uniform_laplace[i / 4][i % 4];
+    }
+    return out_val;
+}
+
+// -1 to 1, scaled by uniform_map_strength before it's added to feed, kill, Da and Db
+fn param_map(uv: vec2<f32>, uvf: vec2<f32>) -> f32
+{
+    if (uniform_map_mode == 1u) {
+        return sin(length(uvf)*10.);
+    }
+    if (uniform_map_mode == 2u) {
+        let c = textureSampleLevel(map_tex, map_samp, uv, 0.).rgb;
+        return dot(c, vec3<f32>(0.299, 0.587, 0.114)) * 2. - 1.;
+    }
+    return 0.;
 }
 
 fn rd1(uv: vec2<f32>) -> vec4<f32>
 {
     let prev = textureSample(prev_tex, prev_samp, uv);
-    let lap = -laplace(uv);
+    let lap = laplace(uv);
 
-    let uvf = ((uv - vec2<f32>(0.5))*2.);
-    let Da = uniform_da;
-    let Db = uniform_db;// + sin(length(uvf)*10.) * 0.15;
-    let f = uniform_feed + sin(length(uvf)*10.)*0.015;
-    let k = uniform_kill + length(uvf)*0.006 + sin(length(uvf)*10.)*0.015;
+    var uvf = ((uv - vec2<f32>(0.5))*2.);
+    uvf *= vec2<f32>(uniform_resolution.x / uniform_resolution.y, 1.);
+    let m = param_map(uv, uvf);
+    let Da = uniform_da * (1. + m * uniform_map_strength.z);
+    let Db = uniform_db * (1. + m * uniform_map_strength.w);
+    let f = uniform_feed + m * uniform_map_strength.x;
+    var k = uniform_kill + m * uniform_map_strength.y;
+    if (uniform_map_mode == 1u) {
+        // The radial map also kills more towards the edges
+        k += length(uvf)*0.006;
+    }
 
     let new_stuff = vec2<f32>(
             Da * lap.r - prev.r * prev.g*prev.g + f * clamp(1.0 - prev.r, 0., 1.),
@@ -103,6 +133,116 @@ fn rd1(uv: vec2<f32>) -> vec4<f32>
     lap.g * 30.);
 }
 
+fn hash2(p: vec2<f32>) -> f32 {
+    return fract(sin(dot(p, vec2<f32>(127.1, 311.7))) * 43758.5453);
+}
+
+fn value_noise(p: vec2<f32>) -> f32 {
+    let i = floor(p);
+    let f = fract(p);
+    let u = f * f * (3.0 - 2.0 * f);
+    return mix(mix(hash2(i), hash2(i + vec2<f32>(1., 0.)), u.x),
+               mix(hash2(i + vec2<f32>(0., 1.)), hash2(i + vec2<f32>(1., 1.)), u.x), u.y);
+}
+
+// Activator u in red, inhibitor v in green
+fn fitzhugh_nagumo(uv: vec2<f32>) -> vec4<f32>
+{
+    let dt = 0.05;
+    let prev = textureSample(prev_tex, prev_samp, uv);
+    let lap = laplace(uv);
+    let u = prev.r;
+    let v = prev.g;
+    let du = uniform_fhn.x * lap.r + u - u*u*u - v;
+    let dv = uniform_fhn.y * lap.g + uniform_fhn.z * (u - 2. * v - uniform_fhn.w);
+    return vec4<f32>(clamp(u + du * dt, -2., 2.), clamp(v + dv * dt, -2., 2.), lap.r * 30., lap.g * 30.);
+}
+
+// X in red, Y in green
+fn brusselator(uv: vec2<f32>) -> vec4<f32>
+{
+    let dt = 0.01;
+    let prev = textureSample(prev_tex, prev_samp, uv);
+    let lap = laplace(uv);
+    let x = prev.r;
+    let y = prev.g;
+    let a = uniform_brusselator.x;
+    let b = uniform_brusselator.y;
+    let dx = uniform_brusselator.z * lap.r + a - (b + 1.) * x + x*x*y;
+    let dy = uniform_brusselator.w * lap.g + b * x - x*x*y;
+    return vec4<f32>(clamp(x + dx * dt, 0., 20.), clamp(y + dy * dt, 0., 20.), lap.r * 30., lap.g * 30.);
+}
+
+fn species(value: f32, count: f32) -> f32 {
+    return floor(value * count + 0.5) % count;
+}
+
+// Species index / count in green, red is its inverse so the field looks like the other models
+fn cyclic(uv: vec2<f32>) -> vec4<f32>
+{
+    let px = 1.0 / uniform_resolution;
+    let count = max(floor(uniform_cyclic.x), 2.);
+    let range = i32(clamp(uniform_cyclic.z, 1., 2.));
+    let own = species(textureSampleLevel(prev_tex, prev_samp, uv, 0.).g, count);
+    let next = (own + 1.) % count;
+
+    var neighbours = 0.;
+    for (var x = -range; x <= range; x = x + 1) {
+        for (var y = -range; y <= range; y = y + 1) {
+            let s = species(textureSampleLevel(prev_tex, prev_samp, uv + vec2<f32>(f32(x), f32(y)) * px, 0.).g, count);
+            neighbours += select(0., 1., s == next);
+        }
+    }
+
+    let chance = hash2(uv * uniform_resolution + fract(globals.time) * 1000.);
+    var state = own;
+    if (neighbours >= uniform_cyclic.y || chance < uniform_cyclic.w) {
+        state = next;
+    }
+    let g = state / count;
+    return vec4<f32>(1. - g, g, neighbours / 24., 0.);
+}
+
+// Alive cells in green, Game of Life when the radius is 1
+fn lenia(uv: vec2<f32>) -> vec4<f32>
+{
+    let px = 1.0 / uniform_resolution;
+    let radius = clamp(uniform_lenia.x, 1., 8.);
+    let r = i32(ceil(radius));
+    let own = textureSampleLevel(prev_tex, prev_samp, uv, 0.).g;
+
+    if (r == 1) {
+        var alive = 0.;
+        for (var x = -1; x <= 1; x = x + 1) {
+            for (var y = -1; y <= 1; y = y + 1) {
+                if (x != 0 || y != 0) {
+                    alive += step(0.5, textureSampleLevel(prev_tex, prev_samp, uv + vec2<f32>(f32(x), f32(y)) * px, 0.).g);
+                }
+            }
+        }
+        let next = select(0., 1., alive == 3. || (own > 0.5 && alive == 2.));
+        return vec4<f32>(1. - next, next, alive / 8., 0.);
+    }
+
+    // Smooth ring kernel
+    var sum = 0.;
+    var weight = 0.;
+    for (var x = -r; x <= r; x = x + 1) {
+        for (var y = -r; y <= r; y = y + 1) {
+            let d = length(vec2<f32>(f32(x), f32(y))) / radius;
+            if (d > 0. && d < 1.) {
+                let k = exp(4. - 1. / (d * (1. - d)));
+                sum += k * textureSampleLevel(prev_tex, prev_samp, uv + vec2<f32>(f32(x), f32(y)) * px, 0.).g;
+                weight += k;
+            }
+        }
+    }
+    let u = sum / max(weight, 1e-6);
+    let growth = 2. * exp(-pow(u - uniform_lenia.y, 2.) / (2. * uniform_lenia.z * uniform_lenia.z)) - 1.;
+    let next = clamp(own + uniform_lenia.w * growth, 0., 1.);
+    return vec4<f32>(1. - next, next, u, 0.);
+}
+
 /*
 vec4 rd1(vec2 uv)
 {
@@ -130,11 +270,43 @@ vec4 rd1(vec2 uv)
 
 
 
+// Actions from src/rd/actions.rs, replace a step
+fn rd_action(uv: vec2<f32>) -> vec4<f32> {
+    if (uniform_action == 1u) {
+        return vec4<f32>(1., 0., 0., 0.);
+    }
+    if (uniform_action == 2u) {
+        // Blobs a few pixels wide, covering about `density` of the field
+        let p = uv * uniform_resolution / 8. + uniform_action_params.x;
+        let n = value_noise(p) * 0.6 + value_noise(p * 2.3) * 0.4;
+        var b = step(1. - uniform_action_params.y, n);
+        if (uniform_model == 3u) {
+            // A random species in every blob
+            let count = max(floor(uniform_cyclic.x), 2.);
+            b *= floor(hash2(floor(p) + uniform_action_params.x) * count) / count;
+        }
+        return vec4<f32>(1., b, 0., 0.);
+    }
+    let prev = textureSample(prev_tex, prev_samp, uv);
+    return vec4<f32>(prev.g, prev.r, prev.b, prev.a);
+}
+
 @fragment
 fn fragment(input: VertexOutput) -> @location(0) vec4<f32> {
     let uv = vec2<f32>(input.uv.x, input.uv.y);
 
-    var output_color = rd1(uv);
+    if (uniform_action != 0u) {
+        return rd_action(uv);
+    }
+
+    var output_color: vec4<f32>;
+    switch (uniform_model) {
+        case 1u: { output_color = fitzhugh_nagumo(uv); }
+        case 2u: { output_color = brusselator(uv); }
+        case 3u: { output_color = cyclic(uv); }
+        case 4u: { output_color = lenia(uv); }
+        default: { output_color = rd1(uv); }
+    }
     //output_color.r = sin(uv.x*100.) * sin(globals.time) * step(uv.y, 0.5);
     //output_color.g += sin(uv.x*100.)*0.01;
     return output_color;
@@ -4,20 +4,37 @@ use bevy_pyree::beat::{BeatCounter, BeatEvent};
 use rand::distributions::Uniform;
 use rand::{random, Rng};
 use crate::beat_controls::BeatMute;
+#[cfg(feature = "feedback")]
 use crate::feedback_shader::FeedbackShaderMaterial;
+#[cfg(feature = "feedback")]
 use crate::feedback_shader::ui::FeedbackControlsAutomation;
+#[cfg(feature = "rd")]
 use crate::rd::ui::WipeAutomationControls;
+#[cfg(feature = "rd")]
+use crate::rd::actions::{RDAction, RDActionAutomation};
+#[cfg(feature = "rd")]
+use crate::rd::seeds::{SeedControls, SeedEvent};
+#[cfg(feature = "rd")]
 use crate::rd::wipes::WipeEvent;
+#[cfg(feature = "rd")]
+use crate::rd::RDResolution;
+#[cfg(feature = "feedback")]
+use crate::status::StatusPanel;
 
 
 pub struct AutomationPlugin;
 
 impl Plugin for AutomationPlugin {
     fn build(&self, app: &mut App) {
-        app
-            .add_system(fb_automation)
-            .add_system(rd_automation)
-        ;
+        // The modules can also be disabled in the config
+        #[cfg(feature = "feedback")]
+        app.add_system(fb_automation.run_if(resource_exists::<FeedbackControlsAutomation>()));
+        #[cfg(feature = "rd")]
+        app.add_system(rd_automation.run_if(resource_exists::<WipeAutomationControls>()));
+        #[cfg(feature = "rd")]
+        app.add_system(rd_seed_automation.run_if(resource_exists::<SeedControls>()));
+        #[cfg(feature = "rd")]
+        app.add_system(rd_action_automation.run_if(resource_exists::<RDActionAutomation>()));
     }
 }
 
@@ -38,6 +55,7 @@ fn pt1_param(u: &mut f32, y: f32, pt1: f32, dt: f32)
     *u = *u + (y - *u) * (dt/(pt1+dt))
 }
 
+#[cfg(feature = "feedback")]
 fn fb_automation(
     mut beat_event_listener: EventReader<BeatEvent>,
     controls: Res<FeedbackControlsAutomation>,
@@ -46,9 +64,13 @@ fn fb_automation(
     time: Res<Time>,
     beat_counter: Res<BeatCounter>,
     beat_mute: Res<BeatMute>,
+    mut status: ResMut<StatusPanel>,
 ) {
-    let mat_handle = mat_query.get_single_mut().unwrap();
-    let mut mat = materials.get_mut(mat_handle).unwrap();
+    let Some(mat) = mat_query.get_single_mut().ok().and_then(|h| materials.get_mut(h)) else {
+        status.error("feedback_automation", "Feedback material not found, automation paused");
+        return;
+    };
+    status.resolve("feedback_automation");
 
     pt1_param(&mut mat.beat_stuff.beatpt1, 0., controls.beatpt1, time.delta_seconds());
     pt1_param(&mut mat.beat_stuff.beataccumpt1, mat.beat_stuff.beataccum, controls.beatpt1, time.delta_seconds());
@@ -103,11 +125,10 @@ fn fb_automation(
     pt1_param(&mut mat.randpt1.p7, mat.rand.p7, controls.pt1[7], time.delta_seconds());
 }
 
+#[cfg(feature = "rd")]
 fn rd_automation(
     mut beat_event_listener: EventReader<BeatEvent>,
     mut controls: ResMut<WipeAutomationControls>,
-    mut materials: ResMut<Assets<FeedbackShaderMaterial>>,
-    mut mat_query: Query<&Handle<FeedbackShaderMaterial>>,
     time: Res<Time>,
     beat_counter: Res<BeatCounter>,
     mut local_event: Local<WipeEvent>,
@@ -146,4 +167,42 @@ fn rd_automation(
          event_writer.send(local_event.clone());
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(feature = "rd")]
+fn rd_seed_automation(
+    mut beat_event_listener: EventReader<BeatEvent>,
+    mut controls: ResMut<SeedControls>,
+    mut event_writer: EventWriter<SeedEvent>,
+    beat_mute: Res<BeatMute>,
+    resolution: Res<RDResolution>,
+) {
+    for _ in &mut beat_event_listener {
+        if beat_mute.mute || !controls.on_beat {
+            continue;
+        }
+        controls.beat_count += 1;
+        if controls.beat_count < controls.beat_div { continue; }
+        controls.beat_count = 0;
+        event_writer.send(controls.event(&resolution));
+    }
+}
+
+#[cfg(feature = "rd")]
+fn rd_action_automation(
+    mut beat_event_listener: EventReader<BeatEvent>,
+    mut controls: ResMut<RDActionAutomation>,
+    mut event_writer: EventWriter<RDAction>,
+    beat_mute: Res<BeatMute>,
+) {
+    for _ in &mut beat_event_listener {
+        let Some(action) = controls.action else { continue; };
+        if beat_mute.mute {
+            continue;
+        }
+        controls.beat_count += 1;
+        if controls.beat_count < controls.beat_div { continue; }
+        controls.beat_count = 0;
+        event_writer.send(action);
+    }
+}
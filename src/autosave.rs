@@ -0,0 +1,210 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+const AUTOSAVE_DIR: &str = "autosave";
+const AUTOSAVE_FILES: usize = 5;
+const AUTOSAVE_INTERVAL: f32 = 30.;
+
+pub struct AutosavePlugin;
+
+impl Plugin for AutosavePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<ModuleStateRegistry>()
+            .insert_resource(AutosaveState::new())
+            .add_system(autosave_system)
+            .add_system(restore_ui)
+            .add_system(restore_system.after(restore_ui))
+        ;
+    }
+}
+
+/// Writes to a temporary file next to `path` and renames it over the target,
+/// so a crash mid-write never leaves a truncated file behind.
+pub fn atomic_write(path: impl AsRef<Path>, contents: &str) -> std::io::Result<()> {
+    let path = path.as_ref();
+    let mut tmp_name = path.file_name().unwrap_or_default().to_owned();
+    tmp_name.push(".tmp");
+    let tmp_path = path.with_file_name(tmp_name);
+
+    let mut file = fs::File::create(&tmp_path)?;
+    file.write_all(contents.as_bytes())?;
+    file.sync_all()?;
+    fs::rename(&tmp_path, path)
+}
+
+/// Parameter state of a module that can be saved and restored
+pub trait ModuleState: Clone + Serialize + DeserializeOwned + Send + Sync + 'static {
+    /// Key of the state in autosave files
+    const KEY: &'static str;
+
+    /// Returns `None` if the module isn't set up yet
+    fn capture(world: &mut World) -> Option<Self>;
+    fn apply(&self, world: &mut World);
+}
+
+#[derive(Clone, Copy)]
+struct StateHandler {
+    key: &'static str,
+    capture: fn(&mut World) -> Option<String>,
+    apply: fn(&mut World, &str) -> Result<(), String>,
+}
+
+fn capture_state<T: ModuleState>(world: &mut World) -> Option<String> {
+    ron::to_string(&T::capture(world)?).ok()
+}
+
+fn apply_state<T: ModuleState>(world: &mut World, s: &str) -> Result<(), String> {
+    let state = ron::from_str::<T>(s).map_err(|e| e.to_string())?;
+    state.apply(world);
+    Ok(())
+}
+
+/// All module states that are part of an autosave
+#[derive(Resource, Default)]
+pub struct ModuleStateRegistry {
+    handlers: Vec<StateHandler>,
+}
+
+pub trait AddModuleState {
+    fn add_module_state<T: ModuleState>(&mut self) -> &mut Self;
+}
+
+impl AddModuleState for App {
+    fn add_module_state<T: ModuleState>(&mut self) -> &mut Self {
+        self.init_resource::<ModuleStateRegistry>();
+        let mut registry = self.world.resource_mut::<ModuleStateRegistry>();
+        if !registry.handlers.iter().any(|h| h.key == T::KEY) {
+            registry.handlers.push(StateHandler {
+                key: T::KEY,
+                capture: capture_state::<T>,
+                apply: apply_state::<T>,
+            });
+        }
+        self
+    }
+}
+
+/// Full parameter state of all modules, each stored as its own RON string so
+/// autosaves from builds with other modules enabled still load.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub saved_at: u64,
+    pub modules: BTreeMap<String, String>,
+}
+
+fn autosave_path(index: usize) -> PathBuf {
+    Path::new(AUTOSAVE_DIR).join(format!("autosave-{}.ron", index))
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/// Returns the index and contents of the most recent readable autosave
+fn load_latest() -> Option<(usize, Snapshot)> {
+    (0..AUTOSAVE_FILES)
+        .filter_map(|i| {
+            let s = fs::read_to_string(autosave_path(i)).ok()?;
+            match ron::from_str::<Snapshot>(&s) {
+                Ok(snapshot) => Some((i, snapshot)),
+                Err(e) => {
+                    warn!("Ignoring unreadable autosave {:?}: {}", autosave_path(i), e);
+                    None
+                }
+            }
+        })
+        .max_by_key(|(_, snapshot)| snapshot.saved_at)
+}
+
+#[derive(Resource)]
+pub struct AutosaveState {
+    pub timer: Timer,
+    pub next_file: usize,
+    /// Autosave found on startup, waiting for the user to restore or discard it.
+    /// Kept in memory since the rotation may overwrite its file in the meantime.
+    pub pending_restore: Option<Snapshot>,
+    pub restore_requested: bool,
+}
+
+impl AutosaveState {
+    fn new() -> Self {
+        let latest = load_latest();
+        Self {
+            timer: Timer::from_seconds(AUTOSAVE_INTERVAL, TimerMode::Repeating),
+            next_file: latest.as_ref().map(|(i, _)| (i + 1) % AUTOSAVE_FILES).unwrap_or(0),
+            pending_restore: latest.map(|(_, snapshot)| snapshot),
+            restore_requested: false,
+        }
+    }
+}
+
+fn autosave_system(world: &mut World) {
+    let delta = world.resource::<Time>().delta();
+    let mut state = world.resource_mut::<AutosaveState>();
+    if !state.timer.tick(delta).just_finished() { return; }
+    let path = autosave_path(state.next_file);
+
+    let handlers = world.resource::<ModuleStateRegistry>().handlers.clone();
+    let modules = handlers.iter()
+        .filter_map(|h| Some((h.key.to_owned(), (h.capture)(world)?)))
+        .collect();
+    let snapshot = Snapshot { saved_at: unix_time(), modules };
+
+    let result = fs::create_dir_all(AUTOSAVE_DIR)
+        .map_err(|e| e.to_string())
+        .and_then(|_| ron::ser::to_string_pretty(&snapshot, ron::ser::PrettyConfig::default()).map_err(|e| e.to_string()))
+        .and_then(|s| atomic_write(&path, &s).map_err(|e| e.to_string()));
+    let mut state = world.resource_mut::<AutosaveState>();
+    match result {
+        Ok(_) => state.next_file = (state.next_file + 1) % AUTOSAVE_FILES,
+        Err(e) => error!("Autosave to {:?} failed: {}", path, e),
+    }
+}
+
+fn restore_ui(
+    mut contexts: EguiContexts,
+    mut state: ResMut<AutosaveState>,
+) {
+    let Some(snapshot) = &state.pending_restore else { return; };
+    let age = unix_time().saturating_sub(snapshot.saved_at);
+
+    let mut restore = false;
+    let mut discard = false;
+    egui::Window::new("Restore Autosave").show(contexts.ctx_mut(), |ui| {
+        ui.label(format!("Found an autosave from {}m {}s ago.", age / 60, age % 60));
+        ui.horizontal(|ui| {
+            restore = ui.button("Restore").clicked();
+            discard = ui.button("Discard").clicked();
+        });
+    });
+
+    if restore {
+        state.restore_requested = true;
+    }
+    if discard {
+        state.pending_restore = None;
+    }
+}
+
+fn restore_system(world: &mut World) {
+    let mut state = world.resource_mut::<AutosaveState>();
+    if !state.restore_requested { return; }
+    state.restore_requested = false;
+    let Some(snapshot) = state.pending_restore.take() else { return; };
+
+    let handlers = world.resource::<ModuleStateRegistry>().handlers.clone();
+    for handler in handlers {
+        let Some(s) = snapshot.modules.get(handler.key) else { continue; };
+        if let Err(e) = (handler.apply)(world, s) {
+            error!("Failed to restore {} from autosave: {}", handler.key, e);
+        }
+    }
+}
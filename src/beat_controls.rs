@@ -1,20 +1,108 @@
 use bevy::prelude::*;
 use bevy_egui::{egui, EguiContexts};
 use bevy_egui::egui::plot::{Line, PlotPoints};
-use crate::traktor_beat::TraktorBeat;
+use bevy_pyree::beat::BeatEvent;
+use serde::Deserialize;
+use crate::config::AppConfig;
+use crate::keybindings::{AddKeyActions, KeyAction, KeyMode};
+use crate::midi::clock::{midi_clock_system, MidiClock};
+use crate::osc::{AddOscControls, OscParam};
+#[cfg(feature = "traktor")]
+use crate::traktor_beat::{traktor_beat_system, TraktorBeat};
 
 
 pub struct BeatControls;
 
 impl Plugin for BeatControls {
     fn build(&self, app: &mut App) {
+        let source = app.world.get_resource::<AppConfig>().map(|c| c.beat.source).unwrap_or_default();
+        // The sources sync the song position before sending their beat, it has to be counted in the same frame
+        let bar_tracker = bar_tracker_system.after(midi_clock_system);
+        #[cfg(feature = "traktor")]
+        let bar_tracker = bar_tracker.after(traktor_beat_system);
         app
             .add_system(beat_ui)
+            .add_system(bar_tracker)
             .insert_resource(BeatMute::default())
+            .insert_resource(source)
+            .init_resource::<BarTracker>()
+            .add_osc_params(vec![
+                OscParam::resource_bool::<BeatMute>("beat/mute", "Ignore beats", |b| b.mute, |b, v| b.mute = v),
+                OscParam::resource_int::<BeatSource>("beat/source", "Active beat source, 0 Traktor, 1 MIDI clock",
+                    0..=BeatSource::ALL.len() as i32 - 1,
+                    |s| s.index() as i32,
+                    |s, v| *s = BeatSource::ALL[v as usize]),
+            ])
+            .add_key_actions(vec![KeyAction::switch(
+                "beat_mute", "Ignore beats", Some("space"), KeyMode::Momentary,
+                |world| world.resource::<BeatMute>().mute,
+                |world, on| world.resource_mut::<BeatMute>().mute = on,
+            )])
         ;
     }
 }
 
+/// Which input sends `BeatEvent`s. Inactive sources keep counting but stay silent.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[repr(usize)]
+pub enum BeatSource {
+    #[default]
+    Traktor,
+    MidiClock,
+}
+
+impl BeatSource {
+    pub const ALL: [BeatSource; 2] = [BeatSource::Traktor, BeatSource::MidiClock];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            BeatSource::Traktor => "Traktor",
+            BeatSource::MidiClock => "MIDI clock",
+        }
+    }
+
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+pub const BEATS_PER_BAR: u32 = 4;
+
+/// Position of the latest beat in the song, counted from the beat events.
+/// Sources that know the song position sync it before sending their beat.
+#[derive(Resource, Default)]
+pub struct BarTracker {
+    pub beat: u32,
+    next: u32,
+}
+
+impl BarTracker {
+    /// The next beat event will be counted as `beat`
+    pub fn sync(&mut self, beat: u32) {
+        self.next = beat;
+    }
+
+    /// 0 based beat within the bar
+    pub fn beat_in_bar(&self) -> u32 {
+        self.beat % BEATS_PER_BAR
+    }
+
+    pub fn bar(&self) -> u32 {
+        self.beat / BEATS_PER_BAR
+    }
+}
+
+pub fn bar_tracker_system(
+    mut event_listener: EventReader<BeatEvent>,
+    mut bar: ResMut<BarTracker>,
+) {
+    for _ in event_listener.iter() {
+        bar.beat = bar.next;
+        bar.next = bar.next.wrapping_add(1);
+    }
+}
+
 #[derive(Resource, Default)]
 pub struct BeatMute{
     pub mute: bool
@@ -22,32 +110,52 @@ pub struct BeatMute{
 
 pub fn beat_ui(
     mut contexts: EguiContexts,
-    mut traktor_beat: ResMut<TraktorBeat>,
-    keys: Res<Input<KeyCode>>,
+    #[cfg(feature = "traktor")]
+    traktor_beat: Option<ResMut<TraktorBeat>>,
     mut beat_mute: ResMut<BeatMute>,
+    mut source: ResMut<BeatSource>,
+    bar: Res<BarTracker>,
+    midi_clock: Res<MidiClock>,
 ) {
     egui::Window::new("Beat").show(contexts.ctx_mut(), |ui| {
-        ui.label(format!("{}", traktor_beat.count));
-        ui.add(egui::ProgressBar::new((traktor_beat.count as f32 / 24.))
-            .show_percentage());
-        ui.add(egui::ProgressBar::new((traktor_beat.last_volume as f32 / 128.))
-            .show_percentage());
-
-        ui.horizontal(|ui| {
-            if ui.button("Decr").clicked() { traktor_beat.count -= 1; }
-            if ui.button("Incr").clicked() { traktor_beat.count += 1; }
-        });
+        egui::ComboBox::from_label("Source")
+            .selected_text(source.name())
+            .show_ui(ui, |ui| {
+                for s in BeatSource::ALL {
+                    ui.selectable_value(&mut *source, s, s.name());
+                }
+            });
+        ui.label(format!("Bar {} beat {}", bar.bar() + 1, bar.beat_in_bar() + 1));
 
         ui.separator();
-
-        ui.checkbox(&mut beat_mute.mute, "Beat Mute");
-        if keys.just_pressed(KeyCode::Space) {
-            beat_mute.mute = true;
+        ui.label(match (&midi_clock.port, midi_clock.running) {
+            (None, _) => "MIDI clock: no signal".to_owned(),
+            (Some(port), true) => format!("MIDI clock: {} playing", port),
+            (Some(port), false) => format!("MIDI clock: {} stopped", port),
+        });
+        if let Some(bpm) = midi_clock.bpm {
+            ui.label(format!("{:.1} BPM", bpm));
         }
-        if keys.just_released(KeyCode::Space) {
-            beat_mute.mute = false;
+        ui.separator();
+
+        #[cfg(feature = "traktor")]
+        if let Some(mut traktor_beat) = traktor_beat {
+            ui.label(format!("{}", traktor_beat.count));
+            ui.add(egui::ProgressBar::new((traktor_beat.count as f32 / 24.))
+                .show_percentage());
+            ui.add(egui::ProgressBar::new((traktor_beat.last_volume as f32 / 128.))
+                .show_percentage());
+
+            ui.horizontal(|ui| {
+                if ui.button("Decr").clicked() { traktor_beat.count -= 1; }
+                if ui.button("Incr").clicked() { traktor_beat.count += 1; }
+            });
+
+            ui.separator();
         }
 
+        ui.checkbox(&mut beat_mute.mute, "Beat Mute");
+
 
     });
 }
\ No newline at end of file
@@ -11,11 +11,18 @@ use bevy::utils::tracing::event;
 use bevy_egui::{egui, EguiContexts};
 use bevy_pyree::beat::BeatEvent;
 use rand::random;
+use serde::{Deserialize, Serialize};
 use crate::{RenderLayers, ShaderRef};
 use crate::AlphaMode::Blend;
+use crate::autosave::ModuleState;
 use crate::beat_controls::BeatMute;
 use crate::chipspin::ChipSpinState::Fixed;
+use crate::midi::{midi_learn_button, MidiMappings};
+use crate::osc::{AddOscControls, OscParam};
+use crate::presets::{AddPresetBank, PresetModule};
 use crate::shape::Quad;
+use crate::shared_textures::SharedTextures;
+use crate::status::{load_font, StatusPanel};
 
 
 pub struct ChipSpin;
@@ -31,8 +38,12 @@ impl Plugin for ChipSpin {
 
             .init_resource::<ChipSpinTexture>()
             .init_resource::<ChipSpinStateResource>()
+        ;
 
-
+        let default_state = ChipSpinStateResource::from_world(&mut app.world);
+        app
+            .add_preset_bank::<ChipSpinStateResource>(PresetModule::ChipSpin, vec![("Default", default_state)])
+            .add_osc_params(osc_params())
         ;
     }
 }
@@ -47,9 +58,36 @@ pub struct ChipSpinTexture {
 
 impl FromWorld for ChipSpinTexture {
     fn from_world(world: &mut World) -> Self {
-        Self {
-            texture: DEFAULT_IMAGE_HANDLE.typed::<Image>()
-        }
+        let mut images = world.get_resource_mut::<Assets<Image>>().unwrap();
+
+        // 3D scene for rendering chip to texture
+        let size = Extent3d { width: 1024, height: 1024, ..default() };
+        let mut dip_render_target_image = Image {
+            texture_descriptor: TextureDescriptor {
+                label: None,
+                size,
+                dimension: TextureDimension::D2,
+                format: TextureFormat::Rgba32Float,
+                mip_level_count: 1,
+                sample_count: 1,
+                usage: TextureUsages::TEXTURE_BINDING
+                    | TextureUsages::COPY_DST
+                    | TextureUsages::RENDER_ATTACHMENT,
+                view_formats: &[]
+            },
+            sampler_descriptor: ImageSampler::Descriptor(SamplerDescriptor {
+                address_mode_u: AddressMode::Repeat,
+                address_mode_v: AddressMode::Repeat,
+                ..Default::default()
+            }),
+            ..default()
+        };
+        dip_render_target_image.resize(size);
+        let texture = images.add(dip_render_target_image);
+
+        world.resource_mut::<SharedTextures>().chip_spin = texture.clone();
+
+        Self { texture }
     }
 }
 
@@ -59,7 +97,9 @@ fn setup(
     mut materials: ResMut<Assets<StandardMaterial>>,
     mut images: ResMut<Assets<Image>>,
     assets: Res<AssetServer>,
-    mut chip_spin_texture: ResMut<ChipSpinTexture>,
+    mut fonts: ResMut<Assets<Font>>,
+    mut status: ResMut<StatusPanel>,
+    chip_spin_texture: Res<ChipSpinTexture>,
 ) {
     // Text on the chip must first be rendered to a texture before it can be rendered in 3d space
     let size = Extent3d { width: 1024, height: 1024, ..default() };
@@ -98,7 +138,7 @@ fn setup(
         ..default()
     }).insert(RenderLayers::layer(8));
 
-    let font = assets.load("fonts/Roboto-Bold.ttf");
+    let font = load_font("fonts/Roboto-Bold.ttf", &assets, &mut fonts, &mut status);
     let text_style1 = TextStyle {
         font: font.clone(),
         font_size: 190.0,
@@ -128,32 +168,7 @@ fn setup(
 
 
     // 3D scene for rendering chip to texture
-    let size = Extent3d { width: 1024, height: 1024, ..default() };
-    let mut dip_render_target_image = Image {
-        texture_descriptor: TextureDescriptor {
-            label: None,
-            size,
-            dimension: TextureDimension::D2,
-            format: TextureFormat::Rgba32Float,
-            mip_level_count: 1,
-            sample_count: 1,
-            usage: TextureUsages::TEXTURE_BINDING
-                | TextureUsages::COPY_DST
-                | TextureUsages::RENDER_ATTACHMENT,
-            view_formats: &[]
-        },
-        sampler_descriptor: ImageSampler::Descriptor(SamplerDescriptor {
-            address_mode_u: AddressMode::Repeat,
-            address_mode_v: AddressMode::Repeat,
-            ..Default::default()
-        }),
-        ..default()
-    };
-    dip_render_target_image.resize(size);
-
-    let dip_render_target_handle = images.add(dip_render_target_image);
-
-    chip_spin_texture.texture = dip_render_target_handle.clone();
+    let dip_render_target_handle = chip_spin_texture.texture.clone();
 
     commands.spawn(Camera3dBundle {
         projection: OrthographicProjection {
@@ -266,14 +281,14 @@ fn recursive_render_layer_insert(
 }
 
 // Chip spin system
-#[derive(Debug, PartialEq, Clone, Copy)]
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
 pub enum ChipSpinState {
     Default, // Default position
     Fixed, // Set fixed rotation
     Rate, // Rotate with rate
 }
 
-#[derive(Resource)]
+#[derive(Resource, Clone, Serialize, Deserialize)]
 pub struct ChipSpinStateResource {
     pub state: ChipSpinState,
     pub fixed: (f32, f32, f32),
@@ -306,14 +321,67 @@ impl FromWorld for ChipSpinStateResource {
     }
 }
 
+impl ModuleState for ChipSpinStateResource {
+    const KEY: &'static str = "chip_spin";
+
+    fn capture(world: &mut World) -> Option<Self> {
+        world.get_resource::<Self>().cloned()
+    }
+
+    fn apply(&self, world: &mut World) {
+        world.insert_resource(self.clone());
+    }
+}
+
+fn axis(v: &mut (f32, f32, f32), i: usize) -> &mut f32 {
+    match i {
+        0 => &mut v.0,
+        1 => &mut v.1,
+        _ => &mut v.2,
+    }
+}
+
+/// `/vj/chip/...`
+fn osc_params() -> Vec<OscParam> {
+    let mut params = vec![
+        OscParam::resource_int::<ChipSpinStateResource>(
+            "chip/mode", "Rotation mode, 0: Default, 1: Fixed, 2: Rate", 0..=2,
+            |c| c.state as i32,
+            |c, v| c.state = match v { 0 => ChipSpinState::Default, 1 => ChipSpinState::Fixed, _ => ChipSpinState::Rate },
+        ),
+        OscParam::resource_bool::<ChipSpinStateResource>("chip/visible", "Show the chip", |c| c.visible, |c, v| c.visible = v),
+        OscParam::resource_bool::<ChipSpinStateResource>("chip/jump", "Jump to the audio volume", |c| c.jump, |c, v| c.jump = v),
+        OscParam::resource::<ChipSpinStateResource>("chip/pt1_strength", "Jump strength", 0. ..=10., |c| c.pt1_strength, |c, v| c.pt1_strength = v),
+        OscParam::resource::<ChipSpinStateResource>("chip/pt1_t", "Jump smoothing time", 0. ..=1000., |c| c.pt1_t, |c, v| c.pt1_t = v),
+        OscParam::resource_bool::<ChipSpinStateResource>("chip/rand", "Randomize rotation on beat", |c| c.rand, |c, v| c.rand = v),
+        OscParam::resource::<ChipSpinStateResource>("chip/rand_range", "Range of random rotations", 0. ..=10., |c| c.rand_range, |c, v| c.rand_range = v),
+    ];
+    for (i, a) in ["x", "y", "z"].into_iter().enumerate() {
+        params.push(OscParam::resource::<ChipSpinStateResource>(
+            format!("chip/fixed/{}", a), format!("Fixed rotation around {}", a), -TAU..=TAU,
+            move |c| [c.fixed.0, c.fixed.1, c.fixed.2][i], move |c, v| *axis(&mut c.fixed, i) = v,
+        ));
+        params.push(OscParam::resource::<ChipSpinStateResource>(
+            format!("chip/rate/{}", a), format!("Rotation rate around {}", a), -10. ..=10.,
+            move |c| [c.rate.0, c.rate.1, c.rate.2][i], move |c, v| *axis(&mut c.rate, i) = v,
+        ));
+    }
+    params
+}
+
 fn spin_dip_system(
     mut query: Query<&mut Transform, With<DipModel>>,
     time: Res<Time>,
     mut csr: ResMut<ChipSpinStateResource>,
     mut event_listener: EventReader<BeatEvent>,
     beat_mute: Res<BeatMute>,
+    mut status: ResMut<StatusPanel>,
 ) {
-    let mut transform = query.single_mut();
+    let Ok(mut transform) = query.get_single_mut() else {
+        status.error("dip_model", "Chip model not found");
+        return;
+    };
+    status.resolve("dip_model");
 
     for event in &mut event_listener {
         if beat_mute.mute { continue; }
@@ -355,8 +423,10 @@ fn chip_spin_ui(
     mut contexts: EguiContexts,
     mut csr: ResMut<ChipSpinStateResource>,
     mut query: Query<&mut Transform, With<DipModel>>,
+    mut midi: ResMut<MidiMappings>,
 ) {
-    let mut transform = query.single_mut();
+    // Missing model is reported by spin_dip_system
+    let Ok(mut transform) = query.get_single_mut() else { return; };
 
     egui::Window::new("Chip Spin").show(contexts.ctx_mut(), |ui| {
         egui::Grid::new("my_grid")
@@ -375,16 +445,22 @@ fn chip_spin_ui(
                     ui.label("Fixed");
                     ui.horizontal(|ui| {
                         ui.add(egui::DragValue::new(&mut csr.fixed.0).speed(0.01).max_decimals(2));
+                        midi_learn_button(ui, &mut midi, "chip/fixed/x");
                         ui.add(egui::DragValue::new(&mut csr.fixed.1).speed(0.01).max_decimals(2));
+                        midi_learn_button(ui, &mut midi, "chip/fixed/y");
                         ui.add(egui::DragValue::new(&mut csr.fixed.2).speed(0.01).max_decimals(2));
+                        midi_learn_button(ui, &mut midi, "chip/fixed/z");
                     });
                     ui.end_row();
 
                     ui.label("Rate");
                     ui.horizontal(|ui| {
                         ui.add(egui::DragValue::new(&mut csr.rate.0).speed(0.01).max_decimals(2));
+                        midi_learn_button(ui, &mut midi, "chip/rate/x");
                         ui.add(egui::DragValue::new(&mut csr.rate.1).speed(0.01).max_decimals(2));
+                        midi_learn_button(ui, &mut midi, "chip/rate/y");
                         ui.add(egui::DragValue::new(&mut csr.rate.2).speed(0.01).max_decimals(2));
+                        midi_learn_button(ui, &mut midi, "chip/rate/z");
                     });
                     ui.end_row();
 
@@ -397,9 +473,18 @@ fn chip_spin_ui(
         ui.separator();
         ui.checkbox(&mut csr.visible, "Show");
         ui.checkbox(&mut csr.jump, "Audio React");
-        ui.add(egui::DragValue::new(&mut csr.pt1_strength).speed(0.01));
-        ui.add(egui::DragValue::new(&mut csr.pt1_t).speed(0.01).clamp_range(0. ..=1000.));
+        ui.horizontal(|ui| {
+            ui.add(egui::DragValue::new(&mut csr.pt1_strength).speed(0.01));
+            midi_learn_button(ui, &mut midi, "chip/pt1_strength");
+        });
+        ui.horizontal(|ui| {
+            ui.add(egui::DragValue::new(&mut csr.pt1_t).speed(0.01).clamp_range(0. ..=1000.));
+            midi_learn_button(ui, &mut midi, "chip/pt1_t");
+        });
         ui.checkbox(&mut csr.rand, "Rand");
-        ui.add(egui::DragValue::new(&mut csr.rand_range).speed(0.01));
+        ui.horizontal(|ui| {
+            ui.add(egui::DragValue::new(&mut csr.rand_range).speed(0.01));
+            midi_learn_button(ui, &mut midi, "chip/rand_range");
+        });
     });
 }
\ No newline at end of file
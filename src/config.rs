@@ -0,0 +1,357 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::str::FromStr;
+use bevy::prelude::*;
+use clap::Parser;
+use serde::Deserialize;
+use crate::beat_controls::BeatSource;
+use crate::keybindings::{KeyBindingConfig, KeyChord};
+use crate::osc::routing::OscRoute;
+
+const DEFAULT_CONFIG_PATH: &str = "vjpyree.toml";
+pub const RD_SIZE_LIMITS: std::ops::RangeInclusive<u32> = 16..=8192;
+
+/// Command line overrides, applied on top of the config file
+#[derive(Parser)]
+#[command(about = "VJ Pyree")]
+pub struct Cli {
+    /// Path to the config file
+    #[arg(long)]
+    pub config: Option<PathBuf>,
+    /// Port to receive OSC on, keeping the configured bind address
+    #[arg(long)]
+    pub port: Option<u16>,
+    /// Scale factor of the control window
+    #[arg(long)]
+    pub scale_factor: Option<f64>,
+    /// Comma separated list of enabled modules, e.g. `chipspin,fractal,rd,feedback`
+    #[arg(long, value_delimiter = ',')]
+    pub modules: Option<Vec<Module>>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+pub enum Module {
+    #[serde(rename = "motto")]
+    Motto,
+    #[serde(rename = "chipspin")]
+    ChipSpin,
+    #[serde(rename = "fractal")]
+    Fractal,
+    #[serde(rename = "rd")]
+    RD,
+    #[serde(rename = "feedback")]
+    Feedback,
+    #[serde(rename = "projection_map")]
+    ProjectionMap,
+    #[serde(rename = "traktor")]
+    Traktor,
+}
+
+impl Module {
+    pub const ALL: [Module; 7] = [Module::Motto, Module::ChipSpin, Module::Fractal, Module::RD, Module::Feedback, Module::ProjectionMap, Module::Traktor];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Module::Motto => "motto",
+            Module::ChipSpin => "chipspin",
+            Module::Fractal => "fractal",
+            Module::RD => "rd",
+            Module::Feedback => "feedback",
+            Module::ProjectionMap => "projection_map",
+            Module::Traktor => "traktor",
+        }
+    }
+
+    /// Modules whose output this one reads
+    pub fn requires(&self) -> &'static [Module] {
+        match self {
+            Module::Motto => &[Module::ChipSpin],
+            Module::Fractal => &[Module::ChipSpin],
+            Module::Feedback => &[Module::Fractal, Module::RD],
+            Module::ProjectionMap => &[Module::ChipSpin, Module::Fractal, Module::RD, Module::Feedback],
+            _ => &[],
+        }
+    }
+
+    /// Whether the module's cargo feature was enabled for this build
+    pub fn compiled(&self) -> bool {
+        match self {
+            Module::Motto => cfg!(feature = "motto"),
+            Module::ChipSpin => cfg!(feature = "chipspin"),
+            Module::Fractal => cfg!(feature = "fractal"),
+            Module::RD => cfg!(feature = "rd"),
+            Module::Feedback => cfg!(feature = "feedback"),
+            Module::ProjectionMap => cfg!(feature = "projection_map"),
+            Module::Traktor => cfg!(feature = "traktor"),
+        }
+    }
+}
+
+impl FromStr for Module {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Module::ALL.into_iter()
+            .find(|m| m.name() == s.trim())
+            .ok_or_else(|| format!(
+                "unknown module '{}', expected one of: {}",
+                s, Module::ALL.map(|m| m.name()).join(", "),
+            ))
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Read(PathBuf, std::io::Error),
+    Parse(PathBuf, toml::de::Error),
+    Invalid(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Read(path, e) => write!(f, "Failed to read config file {:?}: {}", path, e),
+            ConfigError::Parse(path, e) => write!(f, "Invalid config file {:?}: {}", path, e),
+            ConfigError::Invalid(msg) => write!(f, "Invalid configuration: {}", msg),
+        }
+    }
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WindowConfig {
+    pub title: String,
+    pub width: f32,
+    pub height: f32,
+    pub scale_factor: f64,
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        Self {
+            title: "VJ Pyree".into(),
+            width: 1920.,
+            height: 1080.,
+            scale_factor: 1.75,
+        }
+    }
+}
+
+/// Projection output window spawned from the "Projection Map" window
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OutputConfig {
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Default for OutputConfig {
+    fn default() -> Self {
+        Self { width: 1920., height: 1080. }
+    }
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RdConfig {
+    /// Size of the simulation in pixels, can be changed in the "RD Shader" window
+    pub width: u32,
+    pub height: u32,
+    /// Simulation steps per second at speed 1
+    pub steps_per_second: f32,
+    /// Most steps run in one frame
+    pub max_substeps: usize,
+}
+
+impl Default for RdConfig {
+    fn default() -> Self {
+        Self { width: 1024, height: 1024, steps_per_second: 60., max_substeps: 8 }
+    }
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OscConfig {
+    pub bind: String,
+    /// Control surfaces that get sent parameter changes, beats and bars
+    pub feedback_targets: Vec<String>,
+    /// Maximum parameter updates per second
+    pub feedback_rate: f32,
+    /// Port of the OSCQuery HTTP server on localhost, disabled if not set
+    pub query_port: Option<u16>,
+    pub routes: Vec<OscRoute>,
+}
+
+impl Default for OscConfig {
+    fn default() -> Self {
+        Self {
+            bind: "0.0.0.0:31337".into(),
+            feedback_targets: vec![],
+            feedback_rate: 20.,
+            query_port: None,
+            routes: vec![],
+        }
+    }
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MidiConfig {
+    pub enabled: bool,
+    /// Only inputs whose name contains one of these are opened, all if empty
+    pub ports: Vec<String>,
+}
+
+impl Default for MidiConfig {
+    fn default() -> Self {
+        Self { enabled: true, ports: vec![] }
+    }
+}
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BeatConfig {
+    /// Active beat source on startup, can be switched in the "Beat" window
+    pub source: BeatSource,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ModulesConfig {
+    pub enabled: Vec<Module>,
+}
+
+impl Default for ModulesConfig {
+    fn default() -> Self {
+        Self {
+            enabled: Module::ALL.into_iter().filter(|m| *m != Module::Motto).collect(),
+        }
+    }
+}
+
+#[derive(Resource, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AppConfig {
+    pub window: WindowConfig,
+    pub output: OutputConfig,
+    pub rd: RdConfig,
+    pub osc: OscConfig,
+    pub midi: MidiConfig,
+    pub beat: BeatConfig,
+    /// Key per action name, overriding the default keys
+    pub keys: BTreeMap<String, KeyBindingConfig>,
+    pub modules: ModulesConfig,
+}
+
+impl AppConfig {
+    /// Reads the config file and applies the command line overrides.
+    /// A missing file is only an error if its path was given explicitly.
+    pub fn load() -> Result<Self, ConfigError> {
+        let cli = Cli::parse();
+
+        let path = cli.config.clone().unwrap_or_else(|| PathBuf::from(DEFAULT_CONFIG_PATH));
+        let mut config = match fs::read_to_string(&path) {
+            Ok(s) => toml::from_str(&s).map_err(|e| ConfigError::Parse(path.clone(), e))?,
+            Err(e) if cli.config.is_none() && e.kind() == std::io::ErrorKind::NotFound => AppConfig::default(),
+            Err(e) => return Err(ConfigError::Read(path, e)),
+        };
+
+        if let Some(scale_factor) = cli.scale_factor {
+            config.window.scale_factor = scale_factor;
+        }
+        if let Some(modules) = cli.modules {
+            config.modules.enabled = modules;
+        }
+        if let Some(port) = cli.port {
+            let mut addr = config.osc_addr()?;
+            addr.set_port(port);
+            config.osc.bind = addr.to_string();
+        }
+
+        config.validate()?;
+
+        // Lean builds can still use the full config file
+        config.modules.enabled.retain(|module| {
+            if !module.compiled() {
+                eprintln!("Module '{}' is enabled but was not compiled in, enable the '{}' cargo feature to use it", module.name(), module.name());
+            }
+            module.compiled()
+        });
+        // Missing modules are replaced by placeholder textures, whether disabled or left out of the build
+        for module in &config.modules.enabled {
+            for required in module.requires() {
+                if !config.enabled(*required) {
+                    eprintln!("Module '{}' reads the output of '{}', which is not running, it gets a black placeholder instead", module.name(), required.name());
+                }
+            }
+        }
+        Ok(config)
+    }
+
+    pub fn osc_addr(&self) -> Result<SocketAddr, ConfigError> {
+        self.osc.bind.parse().map_err(|e| ConfigError::Invalid(format!(
+            "osc.bind '{}' is not a socket address like 0.0.0.0:31337 ({})", self.osc.bind, e,
+        )))
+    }
+
+    pub fn osc_feedback_targets(&self) -> Result<Vec<SocketAddr>, ConfigError> {
+        self.osc.feedback_targets.iter()
+            .map(|target| target.parse().map_err(|e| ConfigError::Invalid(format!(
+                "osc.feedback_targets entry '{}' is not a socket address like 192.168.0.20:9000 ({})", target, e,
+            ))))
+            .collect()
+    }
+
+    pub fn enabled(&self, module: Module) -> bool {
+        self.modules.enabled.contains(&module)
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        let scale_factor = self.window.scale_factor;
+        if !scale_factor.is_finite() || scale_factor <= 0. {
+            return Err(ConfigError::Invalid(format!("window.scale_factor must be positive, got {}", scale_factor)));
+        }
+        for (name, size) in [("window.width", self.window.width), ("window.height", self.window.height),
+                             ("output.width", self.output.width), ("output.height", self.output.height)] {
+            if !size.is_finite() || size < 1. {
+                return Err(ConfigError::Invalid(format!("{} must be at least 1, got {}", name, size)));
+            }
+        }
+        for (name, size) in [("rd.width", self.rd.width), ("rd.height", self.rd.height)] {
+            if !RD_SIZE_LIMITS.contains(&size) {
+                return Err(ConfigError::Invalid(format!("{} must be between {} and {}, got {}", name, RD_SIZE_LIMITS.start(), RD_SIZE_LIMITS.end(), size)));
+            }
+        }
+        if !self.rd.steps_per_second.is_finite() || self.rd.steps_per_second <= 0. {
+            return Err(ConfigError::Invalid(format!("rd.steps_per_second must be positive, got {}", self.rd.steps_per_second)));
+        }
+        if self.rd.max_substeps == 0 || self.rd.max_substeps > 32 {
+            return Err(ConfigError::Invalid(format!("rd.max_substeps must be between 1 and 32, got {}", self.rd.max_substeps)));
+        }
+        self.osc_addr()?;
+        self.osc_feedback_targets()?;
+        if !self.osc.feedback_rate.is_finite() || self.osc.feedback_rate <= 0. {
+            return Err(ConfigError::Invalid(format!("osc.feedback_rate must be positive, got {}", self.osc.feedback_rate)));
+        }
+        for route in &self.osc.routes {
+            if !route.from.starts_with('/') || !route.to.starts_with('/') {
+                return Err(ConfigError::Invalid(format!("osc.routes addresses must start with '/', got '{}' -> '{}'", route.from, route.to)));
+            }
+            if let Some(scale) = route.scale {
+                if scale.iter().any(|v| !v.is_finite()) || scale[0] == scale[1] {
+                    return Err(ConfigError::Invalid(format!("osc.routes scale of '{}' needs finite values and in_min != in_max", route.from)));
+                }
+            }
+        }
+        for (action, binding) in &self.keys {
+            if binding.key().is_empty() { continue; }
+            binding.key().parse::<KeyChord>()
+                .map_err(|e| ConfigError::Invalid(format!("keys.{}: {}", action, e)))?;
+        }
+
+        Ok(())
+    }
+}
@@ -7,9 +7,12 @@ use bevy::render::texture::ImageSampler;
 use bevy::render::view::RenderLayers;
 
 use bevy_pyree::render::{FSQuad, spawn_fs_quad, spawn_render_image_to_screen};
+use serde::{Deserialize, Serialize};
+use crate::autosave::{AddModuleState, ModuleState};
 use crate::feedback_shader::ui::{FeedbackControlsAutomation, ui_system};
-use crate::fractal::FractalRenderTarget;
-use crate::rd::RDRenderTarget;
+use crate::osc::{material, material_mut, AddOscControls, OscParam, ParamKind};
+use crate::presets::{AddPresetBank, PresetModule};
+use crate::shared_textures::SharedTextures;
 
 
 pub struct FeedbackShaderPlugin;
@@ -24,8 +27,12 @@ impl Plugin for FeedbackShaderPlugin {
 
             .init_resource::<FeedbackShaderRenderTarget>()
             .init_resource::<FeedbackControlsAutomation>()
+            .add_module_state::<FeedbackControlsAutomation>()
+            .add_preset_bank::<FeedbackPreset>(PresetModule::Feedback, FeedbackPreset::factory())
+            .add_osc_params(osc_params())
 
             .add_system(ui_system)
+            .add_system(feedback_textures_system.in_base_set(CoreSet::PostUpdate))
         ;
     }
 }
@@ -63,10 +70,11 @@ impl FromWorld for FeedbackShaderRenderTarget {
             ..default()
         };
         image.resize(size);
+        let render_target = images.add(image);
 
-        Self {
-            render_target: images.add(image),
-        }
+        world.resource_mut::<SharedTextures>().feedback = render_target.clone();
+
+        Self { render_target }
     }
 }
 
@@ -82,6 +90,16 @@ pub struct UniformParams {
     pub p7: f32,
 }
 
+impl UniformParams {
+    pub fn from_array(p: [f32; 8]) -> Self {
+        Self { p0: p[0], p1: p[1], p2: p[2], p3: p[3], p4: p[4], p5: p[5], p6: p[6], p7: p[7] }
+    }
+
+    pub fn to_array(&self) -> [f32; 8] {
+        [self.p0, self.p1, self.p2, self.p3, self.p4, self.p5, self.p6, self.p7]
+    }
+}
+
 #[derive(Clone, Copy, Default, Reflect, FromReflect, ShaderType)]
 pub struct BeatStuff {
     pub beat: f32,
@@ -127,6 +145,135 @@ impl Material for FeedbackShaderMaterial {
     }
 }
 
+#[derive(Clone, Serialize, Deserialize)]
+pub struct FeedbackPreset {
+    pub col_rot: [f32; 4],
+    pub rand: [f32; 8],
+    pub palette: f32,
+    pub mirror_x: f32,
+    pub uv_scale: f32,
+    /// Recalling only sets the palette and keeps the rest of the current state
+    #[serde(default)]
+    pub palette_only: bool,
+}
+
+impl Default for FeedbackPreset {
+    fn default() -> Self {
+        Self {
+            col_rot: [0.5, 0.5, 0.5, 0.02],
+            rand: [0.; 8],
+            palette: 0.,
+            mirror_x: 0.,
+            uv_scale: 1.,
+            palette_only: false,
+        }
+    }
+}
+
+impl FeedbackPreset {
+    /// One preset per palette button
+    fn factory() -> Vec<(&'static str, Self)> {
+        ["Rainbow", "Reddish", "Red/Green", "Hot/Cold", "Straw/Blue", "Freestyle"].into_iter()
+            .enumerate()
+            .map(|(i, name)| (name, FeedbackPreset { palette: i as f32, palette_only: true, ..default() }))
+            .collect()
+    }
+}
+
+impl ModuleState for FeedbackPreset {
+    const KEY: &'static str = "feedback";
+
+    fn capture(world: &mut World) -> Option<Self> {
+        let handle = world.query::<&Handle<FeedbackShaderMaterial>>().get_single(world).ok()?.clone();
+        let mat = world.resource::<Assets<FeedbackShaderMaterial>>().get(&handle)?;
+        Some(Self {
+            col_rot: mat.col_rot.to_array(),
+            rand: mat.rand.to_array(),
+            palette: mat.settings.palette,
+            mirror_x: mat.settings.mirror_x,
+            uv_scale: mat.settings.uv_scale,
+            palette_only: false,
+        })
+    }
+
+    fn apply(&self, world: &mut World) {
+        let Ok(handle) = world.query::<&Handle<FeedbackShaderMaterial>>().get_single(world).map(|h| h.clone()) else { return; };
+        let mut materials = world.resource_mut::<Assets<FeedbackShaderMaterial>>();
+        let Some(mat) = materials.get_mut(&handle) else { return; };
+        mat.settings.palette = self.palette;
+        if self.palette_only { return; }
+        mat.col_rot = Vec4::from_array(self.col_rot);
+        mat.rand = UniformParams::from_array(self.rand);
+        mat.settings.mirror_x = self.mirror_x;
+        mat.settings.uv_scale = self.uv_scale;
+    }
+}
+
+/// `/vj/feedback/...`
+fn osc_params() -> Vec<OscParam> {
+    let mut params = vec![];
+    for (i, c) in ["x", "y", "z", "w"].into_iter().enumerate() {
+        let range = if c == "w" { -0.1..=0.1 } else { 0. ..=1. };
+        params.push(OscParam::material::<FeedbackShaderMaterial>(
+            format!("feedback/col_rot/{}", c), format!("Color rotation {}", c), range,
+            move |m| m.col_rot[i], move |m, v| m.col_rot[i] = v,
+        ));
+    }
+    for i in 0..8 {
+        params.push(OscParam::material::<FeedbackShaderMaterial>(
+            format!("feedback/rand/p{}", i), format!("Random parameter {}", i), 0. ..=1.,
+            move |m| m.rand.to_array()[i],
+            move |m, v| {
+                let mut rand = m.rand.to_array();
+                rand[i] = v;
+                m.rand = UniformParams::from_array(rand);
+            },
+        ));
+    }
+    params.push(OscParam::new(
+        "feedback/palette", "Palette, 0: Rainbow, 1: Reddish, 2: Red/Green, 3: Hot/Cold, 4: Straw/Blue, 5: Freestyle",
+        ParamKind::Int, 0. ..=5.,
+        |world| material::<FeedbackShaderMaterial>(world).map(|m| m.settings.palette),
+        |world, v| if let Some(m) = material_mut::<FeedbackShaderMaterial>(world) { m.settings.palette = v },
+    ));
+    params.push(OscParam::new(
+        "feedback/mirror_x", "Mirror along X", ParamKind::Bool, 0. ..=1.,
+        |world| material::<FeedbackShaderMaterial>(world).map(|m| m.settings.mirror_x),
+        |world, v| if let Some(m) = material_mut::<FeedbackShaderMaterial>(world) { m.settings.mirror_x = v },
+    ));
+    params.push(OscParam::material::<FeedbackShaderMaterial>(
+        "feedback/uv_scale", "UV scale, below 1 zooms in, above 1 zooms out", 0.9..=1.1,
+        |m| m.settings.uv_scale, |m, v| m.settings.uv_scale = v,
+    ));
+
+    for (c, name) in ["r", "g", "b", "w"].into_iter().zip(["red", "green", "blue", "white"]) {
+        params.push(OscParam::resource_bool::<FeedbackControlsAutomation>(
+            format!("feedback/auto/col_rot/{}", c), format!("Randomize {} color rotation on beat", name),
+            move |a| match c { "r" => a.col_r, "g" => a.col_g, "b" => a.col_b, _ => a.col_w },
+            move |a, v| match c { "r" => a.col_r = v, "g" => a.col_g = v, "b" => a.col_b = v, _ => a.col_w = v },
+        ));
+    }
+    for i in 0..8 {
+        params.push(OscParam::resource_bool::<FeedbackControlsAutomation>(
+            format!("feedback/auto/rand/p{}", i), format!("Randomize parameter {} on beat", i),
+            move |a| a.rand[i], move |a, v| a.rand[i] = v,
+        ));
+        params.push(OscParam::resource::<FeedbackControlsAutomation>(
+            format!("feedback/auto/pt1/p{}", i), format!("Smoothing time of parameter {}", i), 0. ..=10.,
+            move |a| a.pt1[i], move |a, v| a.pt1[i] = v,
+        ));
+    }
+    params.push(OscParam::resource::<FeedbackControlsAutomation>(
+        "feedback/auto/beatpt1", "Smoothing time of the beat pulse", 0. ..=10.,
+        |a| a.beatpt1, |a, v| a.beatpt1 = v,
+    ));
+    params.push(OscParam::resource_bool::<FeedbackControlsAutomation>(
+        "feedback/auto/rand_pal", "Random palette on beat",
+        |a| a.rand_pal, |a, v| a.rand_pal = v,
+    ));
+    params
+}
+
 pub fn spawn_feedback_shader(
     mut commands: Commands,
     mut images: ResMut<Assets<Image>>,
@@ -134,13 +281,12 @@ pub fn spawn_feedback_shader(
     mut materials: ResMut<Assets<FeedbackShaderMaterial>>,
     mut std_materials: ResMut<Assets<StandardMaterial>>,
     feedback_rt: Res<FeedbackShaderRenderTarget>,
-    fractal_rt: Res<FractalRenderTarget>,
-    rd_rt: Res<RDRenderTarget>
+    shared_textures: Res<SharedTextures>,
 ) {
     let material_handle = materials.add(FeedbackShaderMaterial {
         previous_rt: feedback_rt.render_target.clone(),
-        fractal_rt: fractal_rt.render_target.clone(),
-        rd_rt: rd_rt.render_target.clone(),
+        fractal_rt: shared_textures.fractal.clone(),
+        rd_rt: shared_textures.rd.clone(),
         col_rot: Vec4::new(0.5, 0.5, 0.5, 0.02),
         rand: UniformParams::default(),
         randpt1: UniformParams::default(),
@@ -167,4 +313,15 @@ pub fn spawn_feedback_shader(
         rt_res.render_target.clone(),
         RenderLayers::layer(31),
     );*/
-}
\ No newline at end of file
+}
+/// The reaction-diffusion module shares whichever of its buffers the last step ended in
+fn feedback_textures_system(
+    shared_textures: Res<SharedTextures>,
+    mat_query: Query<&Handle<FeedbackShaderMaterial>>,
+    mut materials: ResMut<Assets<FeedbackShaderMaterial>>,
+) {
+    if !shared_textures.is_changed() { return; }
+    let Some(mat) = mat_query.get_single().ok().and_then(|h| materials.get_mut(h)) else { return; };
+    mat.fractal_rt = shared_textures.fractal.clone();
+    mat.rd_rt = shared_textures.rd.clone();
+}
@@ -3,8 +3,12 @@ use std::ops::Range;
 
 use crate::feedback_shader::FeedbackShaderMaterial;
 use bevy_egui::{egui, EguiContexts};
+use serde::{Deserialize, Serialize};
+use crate::autosave::ModuleState;
+use crate::midi::{midi_learn_button, MidiMappings};
+use crate::status::StatusPanel;
 
-#[derive(Resource)]
+#[derive(Resource, Clone, Serialize, Deserialize)]
 pub struct FeedbackControlsAutomation {
     pub col_r: bool,
     pub col_g: bool,
@@ -43,14 +47,31 @@ impl FromWorld for FeedbackControlsAutomation {
     }
 }
 
+impl ModuleState for FeedbackControlsAutomation {
+    const KEY: &'static str = "feedback_automation";
+
+    fn capture(world: &mut World) -> Option<Self> {
+        world.get_resource::<Self>().cloned()
+    }
+
+    fn apply(&self, world: &mut World) {
+        world.insert_resource(self.clone());
+    }
+}
+
 pub fn ui_system(
     mut contexts: EguiContexts,
     mut mat_query: Query<&Handle<FeedbackShaderMaterial>>,
     mut materials: ResMut<Assets<FeedbackShaderMaterial>>,
     mut fb_controls_automation: ResMut<FeedbackControlsAutomation>,
+    mut status: ResMut<StatusPanel>,
+    mut midi: ResMut<MidiMappings>,
 ) {
-    let mat_handle = mat_query.get_single_mut().unwrap();
-    let mut mat = materials.get_mut(mat_handle).unwrap();
+    let Some(mat) = mat_query.get_single_mut().ok().and_then(|h| materials.get_mut(h)) else {
+        status.error("feedback_material", "Feedback material not found");
+        return;
+    };
+    status.resolve("feedback_material");
 
     egui::Window::new("Feedback Shader").show(contexts.ctx_mut(), |ui| {
 
@@ -65,6 +86,8 @@ pub fn ui_system(
                 ui.label("Val");
                 ui.label("Rand");
                 ui.label("Range");
+                ui.label("");
+                ui.label("MIDI");
                 ui.end_row();
 
                 ui.label("X");
@@ -72,6 +95,7 @@ pub fn ui_system(
                 ui.add(egui::Checkbox::new(&mut fb_controls_automation.col_r, "Rand"));
                 ui.add(egui::DragValue::new(&mut fb_controls_automation.col_r_range.start).speed(0.01).max_decimals(2));
                 ui.add(egui::DragValue::new(&mut fb_controls_automation.col_r_range.end).speed(0.01).max_decimals(2));
+                midi_learn_button(ui, &mut midi, "feedback/col_rot/x");
                 ui.end_row();
 
                 ui.label("Y");
@@ -79,6 +103,7 @@ pub fn ui_system(
                 ui.add(egui::Checkbox::new(&mut fb_controls_automation.col_g, "Rand"));
                 ui.add(egui::DragValue::new(&mut fb_controls_automation.col_g_range.start).speed(0.01).max_decimals(2));
                 ui.add(egui::DragValue::new(&mut fb_controls_automation.col_g_range.end).speed(0.01).max_decimals(2));
+                midi_learn_button(ui, &mut midi, "feedback/col_rot/y");
                 ui.end_row();
 
                 ui.label("Z");
@@ -86,6 +111,7 @@ pub fn ui_system(
                 ui.add(egui::Checkbox::new(&mut fb_controls_automation.col_b, "Rand"));
                 ui.add(egui::DragValue::new(&mut fb_controls_automation.col_b_range.start).speed(0.01).max_decimals(2));
                 ui.add(egui::DragValue::new(&mut fb_controls_automation.col_b_range.end).speed(0.01).max_decimals(2));
+                midi_learn_button(ui, &mut midi, "feedback/col_rot/z");
                 ui.end_row();
 
                 ui.label("W");
@@ -93,6 +119,7 @@ pub fn ui_system(
                 ui.add(egui::Checkbox::new(&mut fb_controls_automation.col_w, "Rand"));
                 ui.add(egui::DragValue::new(&mut fb_controls_automation.col_w_range.start).speed(0.01).max_decimals(2));
                 ui.add(egui::DragValue::new(&mut fb_controls_automation.col_w_range.end).speed(0.01).max_decimals(2));
+                midi_learn_button(ui, &mut midi, "feedback/col_rot/w");
                 ui.end_row();
             });
         ui.separator();
@@ -107,6 +134,7 @@ pub fn ui_system(
                 ui.label("Min");
                 ui.label("Max");
                 ui.label("T1");
+                ui.label("MIDI");
                 ui.end_row();
 
                 // 0
@@ -116,6 +144,7 @@ pub fn ui_system(
                 ui.add(egui::DragValue::new(&mut fb_controls_automation.rand_range[0].start).speed(0.01).max_decimals(2));
                 ui.add(egui::DragValue::new(&mut fb_controls_automation.rand_range[0].end).speed(0.01).max_decimals(2));
                 ui.add(egui::DragValue::new(&mut fb_controls_automation.pt1[0]).speed(0.01).max_decimals(2).clamp_range(0. ..=f32::INFINITY));
+                midi_learn_button(ui, &mut midi, "feedback/rand/p0");
                 ui.end_row();
 
                 // 1
@@ -125,6 +154,7 @@ pub fn ui_system(
                 ui.add(egui::DragValue::new(&mut fb_controls_automation.rand_range[1].start).speed(0.01).max_decimals(2));
                 ui.add(egui::DragValue::new(&mut fb_controls_automation.rand_range[1].end).speed(0.01).max_decimals(2));
                 ui.add(egui::DragValue::new(&mut fb_controls_automation.pt1[1]).speed(0.01).max_decimals(2).clamp_range(0. ..=f32::INFINITY));
+                midi_learn_button(ui, &mut midi, "feedback/rand/p1");
                 ui.end_row();
 
                 // 2
@@ -134,6 +164,7 @@ pub fn ui_system(
                 ui.add(egui::DragValue::new(&mut fb_controls_automation.rand_range[2].start).speed(0.01).max_decimals(2));
                 ui.add(egui::DragValue::new(&mut fb_controls_automation.rand_range[2].end).speed(0.01).max_decimals(2));
                 ui.add(egui::DragValue::new(&mut fb_controls_automation.pt1[2]).speed(0.01).max_decimals(2).clamp_range(0. ..=f32::INFINITY));
+                midi_learn_button(ui, &mut midi, "feedback/rand/p2");
                 ui.end_row();
 
                 ui.label("3");
@@ -142,6 +173,7 @@ pub fn ui_system(
                 ui.add(egui::DragValue::new(&mut fb_controls_automation.rand_range[3].start).speed(0.01).max_decimals(2));
                 ui.add(egui::DragValue::new(&mut fb_controls_automation.rand_range[3].end).speed(0.01).max_decimals(2));
                 ui.add(egui::DragValue::new(&mut fb_controls_automation.pt1[3]).speed(0.01).max_decimals(2).clamp_range(0. ..=f32::INFINITY));
+                midi_learn_button(ui, &mut midi, "feedback/rand/p3");
                 ui.end_row();
 
                 // 4
@@ -151,6 +183,7 @@ pub fn ui_system(
                 ui.add(egui::DragValue::new(&mut fb_controls_automation.rand_range[4].start).speed(0.01).max_decimals(2));
                 ui.add(egui::DragValue::new(&mut fb_controls_automation.rand_range[4].end).speed(0.01).max_decimals(2));
                 ui.add(egui::DragValue::new(&mut fb_controls_automation.pt1[4]).speed(0.01).max_decimals(2).clamp_range(0. ..=f32::INFINITY));
+                midi_learn_button(ui, &mut midi, "feedback/rand/p4");
                 ui.end_row();
 
                 // 5
@@ -160,6 +193,7 @@ pub fn ui_system(
                 ui.add(egui::DragValue::new(&mut fb_controls_automation.rand_range[5].start).speed(0.01).max_decimals(2));
                 ui.add(egui::DragValue::new(&mut fb_controls_automation.rand_range[5].end).speed(0.01).max_decimals(2));
                 ui.add(egui::DragValue::new(&mut fb_controls_automation.pt1[5]).speed(0.01).max_decimals(2).clamp_range(0. ..=f32::INFINITY));
+                midi_learn_button(ui, &mut midi, "feedback/rand/p5");
                 ui.end_row();
 
                 // 6
@@ -169,6 +203,7 @@ pub fn ui_system(
                 ui.add(egui::DragValue::new(&mut fb_controls_automation.rand_range[6].start).speed(0.01).max_decimals(2));
                 ui.add(egui::DragValue::new(&mut fb_controls_automation.rand_range[6].end).speed(0.01).max_decimals(2));
                 ui.add(egui::DragValue::new(&mut fb_controls_automation.pt1[6]).speed(0.01).max_decimals(2).clamp_range(0. ..=f32::INFINITY));
+                midi_learn_button(ui, &mut midi, "feedback/rand/p6");
                 ui.end_row();
                 
                 ui.label("7");
@@ -177,6 +212,7 @@ pub fn ui_system(
                 ui.add(egui::DragValue::new(&mut fb_controls_automation.rand_range[7].start).speed(0.01).max_decimals(2));
                 ui.add(egui::DragValue::new(&mut fb_controls_automation.rand_range[7].end).speed(0.01).max_decimals(2));
                 ui.add(egui::DragValue::new(&mut fb_controls_automation.pt1[7]).speed(0.01).max_decimals(2).clamp_range(0. ..=f32::INFINITY));
+                midi_learn_button(ui, &mut midi, "feedback/rand/p7");
                 ui.end_row();
 
             });
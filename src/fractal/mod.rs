@@ -5,7 +5,7 @@ use bevy::render::texture::{DEFAULT_IMAGE_HANDLE, ImageSampler};
 use bevy::render::view::RenderLayers;
 
 use bevy_pyree::render::{FSQuad, spawn_fs_quad, spawn_render_image_to_screen};
-use crate::chipspin::ChipSpinTexture;
+use crate::shared_textures::SharedTextures;
 
 
 pub struct FractalPlugin;
@@ -53,10 +53,11 @@ impl FromWorld for FractalRenderTarget {
             ..default()
         };
         image.resize(size);
+        let render_target = images.add(image);
 
-        Self {
-            render_target: images.add(image)
-        }
+        world.resource_mut::<SharedTextures>().fractal = render_target.clone();
+
+        Self { render_target }
     }
 }
 
@@ -89,14 +90,14 @@ pub fn spawn_feedback_shader(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<FractalMaterial>>,
-    chip_spin_texture: Res<ChipSpinTexture>,
+    shared_textures: Res<SharedTextures>,
     fractal_rt: Res<FractalRenderTarget>,
 ) {
 
     let material_handle = materials.add(FractalMaterial {
         previous_rt: fractal_rt.render_target.clone(),
         //image_trap: assets.load("images/trip2.png"),
-        image_trap: chip_spin_texture.texture.clone(),
+        image_trap: shared_textures.chip_spin.clone(),
         julia_c: JuliaC {re: -0.8696, im: 0.26},
     });
 
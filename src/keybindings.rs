@@ -0,0 +1,382 @@
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use bevy_egui::{egui, EguiContexts};
+use serde::Deserialize;
+use crate::config::AppConfig;
+use crate::status::StatusPanel;
+
+/// Names used in the config, the first one of a key is used for display
+const KEY_NAMES: &[(&str, KeyCode)] = &[
+    ("1", KeyCode::Key1), ("2", KeyCode::Key2), ("3", KeyCode::Key3), ("4", KeyCode::Key4), ("5", KeyCode::Key5),
+    ("6", KeyCode::Key6), ("7", KeyCode::Key7), ("8", KeyCode::Key8), ("9", KeyCode::Key9), ("0", KeyCode::Key0),
+    ("a", KeyCode::A), ("b", KeyCode::B), ("c", KeyCode::C), ("d", KeyCode::D), ("e", KeyCode::E),
+    ("f", KeyCode::F), ("g", KeyCode::G), ("h", KeyCode::H), ("i", KeyCode::I), ("j", KeyCode::J),
+    ("k", KeyCode::K), ("l", KeyCode::L), ("m", KeyCode::M), ("n", KeyCode::N), ("o", KeyCode::O),
+    ("p", KeyCode::P), ("q", KeyCode::Q), ("r", KeyCode::R), ("s", KeyCode::S), ("t", KeyCode::T),
+    ("u", KeyCode::U), ("v", KeyCode::V), ("w", KeyCode::W), ("x", KeyCode::X), ("y", KeyCode::Y),
+    ("z", KeyCode::Z),
+    ("f1", KeyCode::F1), ("f2", KeyCode::F2), ("f3", KeyCode::F3), ("f4", KeyCode::F4),
+    ("f5", KeyCode::F5), ("f6", KeyCode::F6), ("f7", KeyCode::F7), ("f8", KeyCode::F8),
+    ("f9", KeyCode::F9), ("f10", KeyCode::F10), ("f11", KeyCode::F11), ("f12", KeyCode::F12),
+    ("space", KeyCode::Space), ("enter", KeyCode::Return), ("return", KeyCode::Return),
+    ("tab", KeyCode::Tab), ("backspace", KeyCode::Back), ("escape", KeyCode::Escape), ("esc", KeyCode::Escape),
+    ("up", KeyCode::Up), ("down", KeyCode::Down), ("left", KeyCode::Left), ("right", KeyCode::Right),
+    ("insert", KeyCode::Insert), ("delete", KeyCode::Delete), ("home", KeyCode::Home), ("end", KeyCode::End),
+    ("pageup", KeyCode::PageUp), ("pagedown", KeyCode::PageDown),
+    ("minus", KeyCode::Minus), ("equals", KeyCode::Equals), ("comma", KeyCode::Comma),
+    ("period", KeyCode::Period), ("slash", KeyCode::Slash), ("grave", KeyCode::Grave),
+    ("numpad0", KeyCode::Numpad0), ("numpad1", KeyCode::Numpad1), ("numpad2", KeyCode::Numpad2),
+    ("numpad3", KeyCode::Numpad3), ("numpad4", KeyCode::Numpad4), ("numpad5", KeyCode::Numpad5),
+    ("numpad6", KeyCode::Numpad6), ("numpad7", KeyCode::Numpad7), ("numpad8", KeyCode::Numpad8),
+    ("numpad9", KeyCode::Numpad9),
+];
+
+pub struct KeyBindingsPlugin;
+
+impl Plugin for KeyBindingsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<KeyBindings>()
+            .init_resource::<CheatSheet>()
+            .add_key_actions(vec![KeyAction::switch(
+                "cheat_sheet", "Show key bindings", Some("f1"), KeyMode::Toggle,
+                |world| world.resource::<CheatSheet>().visible,
+                |world, on| world.resource_mut::<CheatSheet>().visible = on,
+            )])
+            .add_startup_system(bind_keys)
+            .add_system(key_input_system)
+            .add_system(key_action_system.after(key_input_system))
+            .add_system(cheat_sheet_ui)
+        ;
+    }
+}
+
+/// How a key drives a switch: on while held, or flipped on every press
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyMode {
+    Momentary,
+    Toggle,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Modifiers {
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+}
+
+impl Modifiers {
+    pub fn pressed(keys: &Input<KeyCode>) -> Self {
+        Self {
+            ctrl: keys.any_pressed([KeyCode::LControl, KeyCode::RControl]),
+            shift: keys.any_pressed([KeyCode::LShift, KeyCode::RShift]),
+            alt: keys.any_pressed([KeyCode::LAlt, KeyCode::RAlt]),
+        }
+    }
+}
+
+/// A key with the modifiers that have to be held, written like `ctrl+shift+1`.
+/// Modifiers have to match exactly, so `1` doesn't fire while ctrl is held.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct KeyChord {
+    pub key: KeyCode,
+    pub modifiers: Modifiers,
+}
+
+impl FromStr for KeyChord {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut modifiers = Modifiers::default();
+        let mut key = None;
+        for part in s.split('+').map(|p| p.trim().to_lowercase()) {
+            match part.as_str() {
+                "ctrl" | "control" => modifiers.ctrl = true,
+                "shift" => modifiers.shift = true,
+                "alt" => modifiers.alt = true,
+                name => {
+                    if key.is_some() {
+                        return Err(format!("'{}' has more than one key", s));
+                    }
+                    key = Some(KEY_NAMES.iter().find(|(n, _)| *n == name)
+                        .ok_or_else(|| format!("unknown key '{}' in '{}'", name, s))?.1);
+                }
+            }
+        }
+        let key = key.ok_or_else(|| format!("'{}' has no key", s))?;
+        Ok(Self { key, modifiers })
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.ctrl { write!(f, "Ctrl+")?; }
+        if self.modifiers.shift { write!(f, "Shift+")?; }
+        if self.modifiers.alt { write!(f, "Alt+")?; }
+        match KEY_NAMES.iter().find(|(_, k)| *k == self.key) {
+            Some((name, _)) => write!(f, "{}", name.to_uppercase()),
+            None => write!(f, "{:?}", self.key),
+        }
+    }
+}
+
+type Trigger = Arc<dyn Fn(&mut World) + Send + Sync>;
+type SwitchGet = Arc<dyn Fn(&mut World) -> bool + Send + Sync>;
+type SwitchSet = Arc<dyn Fn(&mut World, bool) + Send + Sync>;
+
+#[derive(Clone)]
+enum KeyActionKind {
+    Trigger(Trigger),
+    Switch { mode: KeyMode, get: SwitchGet, set: SwitchSet },
+}
+
+/// Something a key can do, bound by `name` in the `[keys]` section of the config
+#[derive(Clone)]
+pub struct KeyAction {
+    pub name: String,
+    pub description: String,
+    /// Used when the config doesn't bind the action
+    pub default_key: Option<&'static str>,
+    kind: KeyActionKind,
+}
+
+impl KeyAction {
+    /// Runs once per key press
+    pub fn trigger(
+        name: impl Into<String>,
+        description: impl Into<String>,
+        default_key: Option<&'static str>,
+        run: impl Fn(&mut World) + Send + Sync + 'static,
+    ) -> Self {
+        Self { name: name.into(), description: description.into(), default_key, kind: KeyActionKind::Trigger(Arc::new(run)) }
+    }
+
+    /// Turns something on and off, `mode` being the default if the binding doesn't set one
+    pub fn switch(
+        name: impl Into<String>,
+        description: impl Into<String>,
+        default_key: Option<&'static str>,
+        mode: KeyMode,
+        get: impl Fn(&mut World) -> bool + Send + Sync + 'static,
+        set: impl Fn(&mut World, bool) + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            description: description.into(),
+            default_key,
+            kind: KeyActionKind::Switch { mode, get: Arc::new(get), set: Arc::new(set) },
+        }
+    }
+
+    fn default_mode(&self) -> Option<KeyMode> {
+        match &self.kind {
+            KeyActionKind::Trigger(_) => None,
+            KeyActionKind::Switch { mode, .. } => Some(*mode),
+        }
+    }
+
+    fn apply(&self, world: &mut World, mode: Option<KeyMode>, pressed: bool) {
+        match &self.kind {
+            KeyActionKind::Trigger(run) => if pressed { run(world) },
+            KeyActionKind::Switch { get, set, .. } => match mode {
+                Some(KeyMode::Toggle) => if pressed {
+                    let on = get(world);
+                    set(world, !on);
+                },
+                _ => set(world, pressed),
+            },
+        }
+    }
+}
+
+pub struct Binding {
+    /// Index into `KeyBindings::actions`
+    pub action: usize,
+    pub chord: KeyChord,
+    /// `None` for triggers
+    pub mode: Option<KeyMode>,
+    /// Another action was bound to the same keys first, this binding is ignored
+    pub conflict: bool,
+    held: bool,
+}
+
+/// Registered actions and the keys bound to them. Modules register their actions when they're built,
+/// the keys are bound on startup.
+#[derive(Resource, Default)]
+pub struct KeyBindings {
+    pub actions: Vec<KeyAction>,
+    pub bindings: Vec<Binding>,
+    /// Binding index and whether the key was pressed or released
+    fired: Vec<(usize, bool)>,
+}
+
+pub trait AddKeyActions {
+    fn add_key_actions(&mut self, actions: Vec<KeyAction>) -> &mut Self;
+}
+
+impl AddKeyActions for App {
+    fn add_key_actions(&mut self, actions: Vec<KeyAction>) -> &mut Self {
+        self.init_resource::<KeyBindings>();
+        self.world.resource_mut::<KeyBindings>().actions.extend(actions);
+        self
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct CheatSheet {
+    pub visible: bool,
+}
+
+/// Binding of an action in the config, either just the keys or keys and mode
+#[derive(Clone, Deserialize)]
+#[serde(untagged)]
+pub enum KeyBindingConfig {
+    Key(String),
+    WithMode { key: String, mode: KeyMode },
+}
+
+impl KeyBindingConfig {
+    pub fn key(&self) -> &str {
+        match self {
+            KeyBindingConfig::Key(key) | KeyBindingConfig::WithMode { key, .. } => key,
+        }
+    }
+
+    pub fn mode(&self) -> Option<KeyMode> {
+        match self {
+            KeyBindingConfig::Key(_) => None,
+            KeyBindingConfig::WithMode { mode, .. } => Some(*mode),
+        }
+    }
+}
+
+fn bind_keys(
+    config: Res<AppConfig>,
+    mut key_bindings: ResMut<KeyBindings>,
+    mut status: ResMut<StatusPanel>,
+) {
+    let unknown = config.keys.keys()
+        .filter(|name| !key_bindings.actions.iter().any(|a| &a.name == *name))
+        .cloned()
+        .collect::<Vec<_>>();
+    if !unknown.is_empty() {
+        status.warn("keys_unknown", format!("Unknown key actions in config: {}", unknown.join(", ")));
+    }
+
+    let mut bindings = vec![];
+    let mut taken: HashMap<KeyChord, usize> = HashMap::default();
+    let mut conflicts = vec![];
+    for (index, action) in key_bindings.actions.iter().enumerate() {
+        let configured = config.keys.get(&action.name);
+        // An empty key unbinds the action
+        let key = match configured {
+            Some(binding) => binding.key(),
+            None => action.default_key.unwrap_or(""),
+        };
+        if key.is_empty() { continue; }
+
+        // Configured keys are checked when loading the config
+        let chord = match key.parse::<KeyChord>() {
+            Ok(chord) => chord,
+            Err(e) => {
+                status.error("keys_default", format!("Default key of {}: {}", action.name, e));
+                continue;
+            }
+        };
+        let conflict = match taken.get(&chord) {
+            Some(&other) => {
+                conflicts.push(format!("{} ({} and {})", chord, key_bindings.actions[other].name, action.name));
+                true
+            }
+            None => {
+                taken.insert(chord, index);
+                false
+            }
+        };
+        let mode = action.default_mode().map(|default| configured.and_then(|b| b.mode()).unwrap_or(default));
+        bindings.push(Binding { action: index, chord, mode, conflict, held: false });
+    }
+
+    if !conflicts.is_empty() {
+        status.warn("keys_conflict", format!("Conflicting key bindings, only the first is used: {}", conflicts.join(", ")));
+    }
+    key_bindings.bindings = bindings;
+}
+
+fn key_input_system(
+    keys: Res<Input<KeyCode>>,
+    mut contexts: EguiContexts,
+    mut key_bindings: ResMut<KeyBindings>,
+) {
+    let typing = contexts.ctx_mut().wants_keyboard_input();
+    let modifiers = Modifiers::pressed(&keys);
+
+    let KeyBindings { bindings, fired, .. } = &mut *key_bindings;
+    for (index, binding) in bindings.iter_mut().enumerate() {
+        if binding.conflict { continue; }
+        // Plain keys are left to text fields while typing, e.g. a preset name
+        let typed = typing && !modifiers.ctrl && !modifiers.alt;
+        if !typed && binding.chord.modifiers == modifiers && keys.just_pressed(binding.chord.key) {
+            binding.held = true;
+            fired.push((index, true));
+        }
+        // Released regardless of modifiers, so a momentary switch can't get stuck
+        if binding.held && keys.just_released(binding.chord.key) {
+            binding.held = false;
+            fired.push((index, false));
+        }
+    }
+}
+
+fn key_action_system(world: &mut World) {
+    let fired = std::mem::take(&mut world.resource_mut::<KeyBindings>().fired);
+    if fired.is_empty() { return; }
+
+    world.resource_scope(|world, key_bindings: Mut<KeyBindings>| {
+        for (index, pressed) in fired {
+            let binding = &key_bindings.bindings[index];
+            key_bindings.actions[binding.action].apply(world, binding.mode, pressed);
+        }
+    });
+}
+
+fn cheat_sheet_ui(
+    mut contexts: EguiContexts,
+    cheat_sheet: Res<CheatSheet>,
+    key_bindings: Res<KeyBindings>,
+) {
+    if !cheat_sheet.visible { return; }
+
+    egui::Area::new("cheat sheet")
+        .anchor(egui::Align2::CENTER_CENTER, [0., 0.])
+        .show(contexts.ctx_mut(), |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                ui.heading("Keys");
+                egui::Grid::new("key bindings")
+                    .num_columns(3)
+                    .spacing([20.0, 4.0])
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for binding in &key_bindings.bindings {
+                            let action = &key_bindings.actions[binding.action];
+                            let key = binding.chord.to_string();
+                            match binding.conflict {
+                                true => ui.colored_label(egui::Color32::RED, key).on_hover_text("Conflicts with another binding"),
+                                false => ui.strong(key),
+                            };
+                            ui.label(&action.description);
+                            ui.label(match binding.mode {
+                                Some(KeyMode::Momentary) => "hold",
+                                Some(KeyMode::Toggle) => "toggle",
+                                None => "",
+                            });
+                            ui.end_row();
+                        }
+                    });
+            });
+        });
+}
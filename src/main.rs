@@ -1,20 +1,34 @@
 //! Full screen shader effect
 
+#[cfg(feature = "feedback")]
 mod feedback_shader;
+#[cfg(feature = "fractal")]
 mod fractal;
+#[cfg(feature = "motto")]
 mod motto;
+#[cfg(feature = "chipspin")]
 mod chipspin;
+#[cfg(feature = "rd")]
 mod rd;
+#[cfg(feature = "projection_map")]
 mod projection_map;
 mod automation;
 mod beat_controls;
+#[cfg(feature = "traktor")]
 mod traktor_beat;
+mod presets;
+mod autosave;
+mod config;
+mod shared_textures;
+mod status;
+mod osc;
+mod midi;
+mod keybindings;
 
 use bevy::prelude::*;
 use bevy::render::render_resource::{AddressMode, Extent3d, SamplerDescriptor, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages};
 use bevy::render::view::RenderLayers;
 use bevy_egui::EguiPlugin;
-use bevy_rosc::BevyRoscPlugin;
 use bevy_pyree::render::{FSQuad, spawn_fs_quad, spawn_render_image_to_screen};
 use bevy::reflect::TypeUuid;
 use bevy::{
@@ -27,17 +41,51 @@ use bevy::window::WindowResolution;
 use bevy_inspector_egui::quick::WorldInspectorPlugin;
 use bevy_pyree::beat::{BeatEvent, OscBeatReceiverPlugin};
 use crate::automation::AutomationPlugin;
+use crate::autosave::AutosavePlugin;
 use crate::beat_controls::BeatControls;
+#[cfg(feature = "chipspin")]
 use crate::chipspin::ChipSpin;
+use crate::config::{AppConfig, Module};
+#[cfg(feature = "feedback")]
 use crate::feedback_shader::FeedbackShaderPlugin;
+#[cfg(feature = "fractal")]
 use crate::fractal::FractalPlugin;
+#[cfg(feature = "motto")]
 use crate::motto::Motto;
+use crate::presets::PresetPlugin;
+#[cfg(feature = "projection_map")]
 use crate::projection_map::ProjectionMapPlugin;
+#[cfg(feature = "rd")]
 use crate::rd::RDPlugin;
+use crate::shared_textures::SharedTextures;
+use crate::status::StatusPlugin;
+use crate::osc::OscControlPlugin;
+use crate::midi::MidiPlugin;
+use crate::keybindings::KeyBindingsPlugin;
+use crate::osc::feedback::OscFeedbackPlugin;
+use crate::osc::monitor::OscMonitorPlugin;
+use crate::osc::query::OscQueryPlugin;
+use crate::osc::server::{OscServer, OscServerPlugin};
+#[cfg(feature = "traktor")]
 use crate::traktor_beat::TraktorPlugin;
 
 
 fn main() {
+    let config = match AppConfig::load() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    let osc_server = match OscServer::bind(&config.osc.bind) {
+        Ok(server) => server,
+        Err(e) => {
+            eprintln!("Failed to receive OSC on {}: {}", config.osc.bind, e);
+            std::process::exit(1);
+        }
+    };
+
     let mut app = App::new();
     app
         .add_plugins(DefaultPlugins
@@ -47,10 +95,10 @@ fn main() {
             })
             .set(WindowPlugin {
                 primary_window: Some(Window {
-                    title: "VJ Pyree".into(),
+                    title: config.window.title.clone(),
                     position: WindowPosition::Centered(MonitorSelection::Primary),
-                    resolution: WindowResolution::new(1920., 1080.)
-                        .with_scale_factor_override(1.75),
+                    resolution: WindowResolution::new(config.window.width, config.window.height)
+                        .with_scale_factor_override(config.window.scale_factor),
                     ..default()
                 }),
                 ..default()
@@ -58,21 +106,44 @@ fn main() {
         )
         .add_plugin(EguiPlugin)
         .add_plugin(WorldInspectorPlugin::default())
+        // Modules read their settings while they're built
+        .insert_resource(config.clone())
 
-        // Send out a beat event once a second
-        // Uncomment this if you want to receive OSC beat events instead
-        .add_plugin(BevyRoscPlugin::new("0.0.0.0:31337").unwrap())
+        .insert_resource(osc_server)
+        .add_plugin(OscServerPlugin)
+        .add_plugin(OscMonitorPlugin)
         .add_plugin(OscBeatReceiverPlugin::default())
 
-        //.add_plugin(Motto)
-        .add_plugin(ChipSpin)
-        .add_plugin(FractalPlugin)
-        .add_plugin(RDPlugin)
-        .add_plugin(FeedbackShaderPlugin)
-        .add_plugin(ProjectionMapPlugin)
+        .init_resource::<SharedTextures>()
+        .add_plugin(StatusPlugin)
+        .add_plugin(KeyBindingsPlugin)
+        .add_plugin(OscControlPlugin)
+        .add_plugin(OscFeedbackPlugin)
+        .add_plugin(OscQueryPlugin)
+        .add_plugin(MidiPlugin)
+        .add_plugin(PresetPlugin)
+        .add_plugin(AutosavePlugin)
+    ;
+
+    // Order matters, later modules read the render targets of earlier ones.
+    // Disabled modules leave a placeholder in `SharedTextures`.
+    #[cfg(feature = "chipspin")]
+    if config.enabled(Module::ChipSpin) { app.add_plugin(ChipSpin); }
+    #[cfg(feature = "motto")]
+    if config.enabled(Module::Motto) { app.add_plugin(Motto); }
+    #[cfg(feature = "fractal")]
+    if config.enabled(Module::Fractal) { app.add_plugin(FractalPlugin); }
+    #[cfg(feature = "rd")]
+    if config.enabled(Module::RD) { app.add_plugin(RDPlugin); }
+    #[cfg(feature = "feedback")]
+    if config.enabled(Module::Feedback) { app.add_plugin(FeedbackShaderPlugin); }
+    #[cfg(feature = "projection_map")]
+    if config.enabled(Module::ProjectionMap) { app.add_plugin(ProjectionMapPlugin); }
+    #[cfg(feature = "traktor")]
+    if config.enabled(Module::Traktor) { app.add_plugin(TraktorPlugin); }
 
+    app
         .add_plugin(AutomationPlugin)
-        .add_plugin(TraktorPlugin)
         .add_plugin(BeatControls)
     ;
 
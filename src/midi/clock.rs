@@ -0,0 +1,71 @@
+use bevy::prelude::*;
+use bevy_pyree::beat::{BeatCounter, BeatEvent};
+use crate::beat_controls::{BarTracker, BeatSource};
+use crate::midi::{MidiEvent, MidiMessage};
+
+/// MIDI clock runs at 24 pulses per quarter note, like the traktor beat ticks
+pub const TICKS_PER_BEAT: u32 = 24;
+/// The song position pointer counts 16th notes
+const TICKS_PER_SIXTEENTH: u32 = 6;
+
+#[derive(Resource, Default)]
+pub struct MidiClock {
+    pub running: bool,
+    /// Position of the next clock tick since the start of the song
+    pub tick: u32,
+    /// Port the last clock tick came from
+    pub port: Option<String>,
+    pub bpm: Option<f32>,
+    last_beat: Option<f64>,
+}
+
+/// Turns clock ticks into beats. The first tick after Start is the first beat of the song.
+/// After Stop, a song position message moves where Continue resumes.
+pub fn midi_clock_system(
+    mut midi_events: EventReader<MidiEvent>,
+    mut clock: ResMut<MidiClock>,
+    mut event_writer: EventWriter<BeatEvent>,
+    mut bar: ResMut<BarTracker>,
+    beat_counter: Res<BeatCounter>,
+    source: Res<BeatSource>,
+    time: Res<Time>,
+) {
+    for event in midi_events.iter() {
+        match event.message {
+            MidiMessage::Start => {
+                clock.running = true;
+                clock.tick = 0;
+                clock.last_beat = None;
+            }
+            MidiMessage::Continue => {
+                clock.running = true;
+                clock.last_beat = None;
+            }
+            MidiMessage::Stop => clock.running = false,
+            MidiMessage::SongPosition(position) if !clock.running => {
+                clock.tick = position as u32 * TICKS_PER_SIXTEENTH;
+            }
+            MidiMessage::Clock => {
+                clock.port = Some(event.port.clone());
+                if !clock.running { continue; }
+
+                if clock.tick % TICKS_PER_BEAT == 0 {
+                    let now = time.elapsed_seconds_f64();
+                    if let Some(last) = clock.last_beat {
+                        // Ticks are only seen once per frame, so smooth out the frame jitter
+                        let bpm = (60. / (now - last)) as f32;
+                        clock.bpm = Some(clock.bpm.map_or(bpm, |old| old * 0.8 + bpm * 0.2));
+                    }
+                    clock.last_beat = Some(now);
+
+                    if *source == BeatSource::MidiClock {
+                        bar.sync(clock.tick / TICKS_PER_BEAT);
+                        event_writer.send(BeatEvent { count: beat_counter.count, bpm: None });
+                    }
+                }
+                clock.tick = clock.tick.wrapping_add(1);
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,478 @@
+pub mod clock;
+
+use std::fs;
+use std::sync::{Arc, Mutex};
+use bevy::ecs::event::ManualEventReader;
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use midir::{Ignore, MidiInput, MidiInputConnection};
+use serde::{Deserialize, Serialize};
+use crate::autosave::atomic_write;
+use crate::config::AppConfig;
+use crate::midi::clock::{midi_clock_system, MidiClock};
+use crate::osc::{OscControls, OscParam, ParamKind};
+use crate::status::StatusPanel;
+
+const MAPPINGS_PATH: &str = "midi_mappings.ron";
+const CLIENT_NAME: &str = "VJ Pyree";
+/// Distance in normalized units within which a control picks up a parameter
+const PICKUP_THRESHOLD: f32 = 0.03;
+
+pub struct MidiPlugin;
+
+impl Plugin for MidiPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_event::<MidiEvent>()
+            .init_resource::<MidiQueue>()
+            .init_resource::<MidiPorts>()
+            .init_resource::<MidiMappings>()
+            .init_resource::<MidiClock>()
+            .add_startup_system(connect_midi_inputs)
+            .add_system(midi_receive_system)
+            .add_system(midi_mapping_system.after(midi_receive_system))
+            .add_system(midi_clock_system.after(midi_receive_system))
+            .add_system(midi_ui)
+        ;
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MidiMessage {
+    NoteOn { channel: u8, note: u8, velocity: u8 },
+    NoteOff { channel: u8, note: u8 },
+    ControlChange { channel: u8, controller: u8, value: u8 },
+    /// 14 bit, centered at 8192
+    PitchBend { channel: u8, value: u16 },
+    Clock,
+    Start,
+    Continue,
+    Stop,
+    /// Position in 16th notes
+    SongPosition(u16),
+}
+
+impl MidiMessage {
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        let status = *bytes.first()?;
+        let data = |i: usize| bytes.get(i).map(|b| b & 0x7f);
+        let channel = status & 0x0f;
+        Some(match status {
+            0xf8 => MidiMessage::Clock,
+            0xfa => MidiMessage::Start,
+            0xfb => MidiMessage::Continue,
+            0xfc => MidiMessage::Stop,
+            0xf2 => MidiMessage::SongPosition(data(1)? as u16 | (data(2)? as u16) << 7),
+            0x80..=0x8f => MidiMessage::NoteOff { channel, note: data(1)? },
+            0x90..=0x9f => match data(2)? {
+                0 => MidiMessage::NoteOff { channel, note: data(1)? },
+                velocity => MidiMessage::NoteOn { channel, note: data(1)?, velocity },
+            },
+            0xb0..=0xbf => MidiMessage::ControlChange { channel, controller: data(1)?, value: data(2)? },
+            0xe0..=0xef => MidiMessage::PitchBend { channel, value: data(1)? as u16 | (data(2)? as u16) << 7 },
+            _ => return None,
+        })
+    }
+
+    /// The control this message belongs to and its value, normalized to 0..1
+    pub fn control(&self) -> Option<(MidiControl, f32)> {
+        match *self {
+            MidiMessage::NoteOn { channel, note, .. } => Some((MidiControl::Note { channel, note }, 1.)),
+            MidiMessage::NoteOff { channel, note } => Some((MidiControl::Note { channel, note }, 0.)),
+            MidiMessage::ControlChange { channel, controller, value } => Some((MidiControl::CC { channel, controller }, value as f32 / 127.)),
+            MidiMessage::PitchBend { channel, value } => Some((MidiControl::PitchBend { channel }, value as f32 / 16383.)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct MidiEvent {
+    pub port: String,
+    pub message: MidiMessage,
+}
+
+/// Messages received by the MIDI threads, turned into `MidiEvent`s every frame.
+/// Pushing to it from anywhere behaves like a message from a real port.
+#[derive(Resource, Clone, Default)]
+pub struct MidiQueue(Arc<Mutex<Vec<MidiEvent>>>);
+
+impl MidiQueue {
+    pub fn push(&self, port: &str, message: MidiMessage) {
+        if let Ok(mut queue) = self.0.lock() {
+            queue.push(MidiEvent { port: port.to_owned(), message });
+        }
+    }
+
+    fn take(&self) -> Vec<MidiEvent> {
+        self.0.lock().map(|mut queue| std::mem::take(&mut *queue)).unwrap_or_default()
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct MidiPorts {
+    pub connected: Vec<String>,
+    pub last_message: Option<MidiEvent>,
+}
+
+/// Keeps the ports open, the connections aren't `Sync`
+struct MidiConnections(Vec<MidiInputConnection<()>>);
+
+fn connect_midi_inputs(world: &mut World) {
+    let config = world.resource::<AppConfig>().midi.clone();
+    if !config.enabled { return; }
+    let queue = world.resource::<MidiQueue>().clone();
+
+    let names = match MidiInput::new(CLIENT_NAME) {
+        Ok(input) => input.ports().iter().filter_map(|p| input.port_name(p).ok()).collect::<Vec<_>>(),
+        Err(e) => {
+            world.resource_mut::<StatusPanel>().warn("midi", format!("MIDI not available: {}", e));
+            return;
+        }
+    };
+
+    let mut connections = vec![];
+    let mut connected = vec![];
+    for name in names {
+        if !config.ports.is_empty() && !config.ports.iter().any(|p| name.contains(p.as_str())) { continue; }
+
+        // Connecting consumes the input, so every port gets its own
+        let Ok(mut input) = MidiInput::new(CLIENT_NAME) else { continue; };
+        input.ignore(Ignore::None);
+        let Some(port) = input.ports().into_iter().find(|p| input.port_name(p).ok().as_ref() == Some(&name)) else { continue; };
+        let queue = queue.clone();
+        let port_name = name.clone();
+        let callback = move |_: u64, bytes: &[u8], _: &mut ()| {
+            if let Some(message) = MidiMessage::parse(bytes) {
+                queue.push(&port_name, message);
+            }
+        };
+        match input.connect(&port, "vjpyree-in", callback, ()) {
+            Ok(connection) => {
+                info!("Connected MIDI input {}", name);
+                connections.push(connection);
+                connected.push(name);
+            }
+            Err(e) => world.resource_mut::<StatusPanel>().warn("midi", format!("Failed to open MIDI input {}: {}", name, e)),
+        }
+    }
+
+    world.resource_mut::<MidiPorts>().connected = connected;
+    world.insert_non_send_resource(MidiConnections(connections));
+}
+
+fn midi_receive_system(
+    queue: Res<MidiQueue>,
+    mut ports: ResMut<MidiPorts>,
+    mut event_writer: EventWriter<MidiEvent>,
+) {
+    let events = queue.take();
+    if let Some(last) = events.iter().rev().find(|e| e.message != MidiMessage::Clock) {
+        ports.last_message = Some(last.clone());
+    }
+    event_writer.send_batch(events);
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum MidiControl {
+    CC { channel: u8, controller: u8 },
+    Note { channel: u8, note: u8 },
+    PitchBend { channel: u8 },
+}
+
+impl MidiControl {
+    pub fn label(&self) -> String {
+        match self {
+            MidiControl::CC { channel, controller } => format!("CC{} ch{}", controller, channel + 1),
+            MidiControl::Note { channel, note } => format!("N{} ch{}", note, channel + 1),
+            MidiControl::PitchBend { channel } => format!("PB ch{}", channel + 1),
+        }
+    }
+}
+
+/// Drives the OSC param at `param` (e.g. `rd/feed`) from a MIDI control
+#[derive(Clone, Serialize, Deserialize)]
+pub struct MidiMapping {
+    pub control: MidiControl,
+    pub param: String,
+    /// Soft takeover: the control only takes effect once it reached the current value
+    #[serde(skip)]
+    pub picked_up: bool,
+    #[serde(skip)]
+    last_control: Option<f32>,
+    #[serde(skip)]
+    last_set: Option<f32>,
+}
+
+impl MidiMapping {
+    fn apply(&mut self, param: &OscParam, world: &mut World, value: f32) {
+        let (min, max) = param.current_range(world).into_inner();
+        let previous = self.last_control.replace(value);
+
+        if let MidiControl::Note { .. } = self.control {
+            // Pads toggle bools and jump to the range ends otherwise
+            match param.kind {
+                ParamKind::Bool if value > 0. => {
+                    let current = param.get(world).unwrap_or(0.);
+                    param.set(world, 1. - current);
+                }
+                ParamKind::Bool => {}
+                _ => param.set(world, min + value * (max - min)),
+            }
+            return;
+        }
+
+        let Some(current) = param.get(world) else { return; };
+        // Moved by something else since, e.g. automation or a preset
+        if self.last_set != Some(current) {
+            self.picked_up = false;
+        }
+        if !self.picked_up {
+            let current = (current - min) / (max - min);
+            let crossed = previous.map_or(false, |p| (p - current).signum() != (value - current).signum());
+            if (value - current).abs() > PICKUP_THRESHOLD && !crossed { return; }
+            self.picked_up = true;
+        }
+
+        param.set(world, min + value * (max - min));
+        self.last_set = param.get(world);
+    }
+}
+
+/// Mappings, persisted to `midi_mappings.ron`
+#[derive(Resource)]
+pub struct MidiMappings {
+    pub mappings: Vec<MidiMapping>,
+    /// Param that gets mapped to the next control moved
+    pub learn: Option<String>,
+    /// File the mappings are saved to, `None` keeps them in memory
+    pub path: Option<&'static str>,
+}
+
+impl FromWorld for MidiMappings {
+    fn from_world(_world: &mut World) -> Self {
+        let mappings = match fs::read_to_string(MAPPINGS_PATH) {
+            Ok(s) => ron::from_str(&s).unwrap_or_else(|e| {
+                error!("Failed to parse {}, starting without MIDI mappings: {}", MAPPINGS_PATH, e);
+                vec![]
+            }),
+            Err(_) => vec![],
+        };
+        Self { mappings, learn: None, path: Some(MAPPINGS_PATH) }
+    }
+}
+
+impl MidiMappings {
+    /// Maps `control` to `param`. Each control drives one param and each param is driven by one control.
+    /// The control was just moved to learn it, so it takes over from `current` without a pickup.
+    pub fn map(&mut self, control: MidiControl, param: String, current: Option<f32>) {
+        self.mappings.retain(|m| m.control != control && m.param != param);
+        self.mappings.push(MidiMapping { control, param, picked_up: true, last_control: None, last_set: current });
+        self.save();
+    }
+
+    pub fn unmap(&mut self, param: &str) {
+        self.mappings.retain(|m| m.param != param);
+        self.save();
+    }
+
+    pub fn save(&self) {
+        let Some(path) = self.path else { return; };
+        let result = ron::ser::to_string_pretty(&self.mappings, ron::ser::PrettyConfig::default())
+            .map_err(|e| e.to_string())
+            .and_then(|s| atomic_write(path, &s).map_err(|e| e.to_string()));
+        if let Err(e) = result {
+            error!("Failed to save MIDI mappings: {}", e);
+        }
+    }
+}
+
+fn midi_mapping_system(
+    world: &mut World,
+    mut reader: Local<ManualEventReader<MidiEvent>>,
+) {
+    let events: Vec<MidiEvent> = reader.iter(world.resource::<Events<MidiEvent>>()).cloned().collect();
+    if events.is_empty() { return; }
+
+    world.resource_scope(|world, mut midi: Mut<MidiMappings>| {
+        world.resource_scope(|world, controls: Mut<OscControls>| {
+            for event in events {
+                let Some((control, value)) = event.message.control() else { continue; };
+                if let Some(path) = midi.learn.take() {
+                    let current = controls.params.iter().find(|p| p.path == path).and_then(|p| p.get(world));
+                    midi.map(control, path, current);
+                    continue;
+                }
+                for mapping in midi.mappings.iter_mut().filter(|m| m.control == control) {
+                    let Some(param) = controls.params.iter().find(|p| p.path == mapping.param) else { continue; };
+                    mapping.apply(param, world, value);
+                }
+            }
+        });
+    });
+}
+
+/// Learn button for the param at `path`. Shows the mapped control, right click removes the mapping.
+pub fn midi_learn_button(ui: &mut egui::Ui, midi: &mut MidiMappings, path: &str) {
+    let learning = midi.learn.as_deref() == Some(path);
+    let label = match midi.mappings.iter().find(|m| m.param == path) {
+        _ if learning => "...".to_owned(),
+        Some(mapping) => mapping.control.label(),
+        None => "Learn".to_owned(),
+    };
+    let response = ui.selectable_label(learning, label).on_hover_text("MIDI learn, right click to unmap");
+    if response.clicked() {
+        midi.learn = if learning { None } else { Some(path.to_owned()) };
+    }
+    if response.secondary_clicked() {
+        midi.unmap(path);
+    }
+}
+
+fn midi_ui(
+    mut contexts: EguiContexts,
+    ports: Res<MidiPorts>,
+    mut midi: ResMut<MidiMappings>,
+) {
+    egui::Window::new("MIDI").default_open(false).show(contexts.ctx_mut(), |ui| {
+        if ports.connected.is_empty() {
+            ui.label("No MIDI inputs connected");
+        }
+        for port in &ports.connected {
+            ui.label(port);
+        }
+        if let Some(event) = &ports.last_message {
+            ui.label(format!("Last: {:?} from {}", event.message, event.port));
+        }
+
+        ui.separator();
+        if let Some(param) = midi.learn.clone() {
+            ui.horizontal(|ui| {
+                ui.label(format!("Move a control to map {}", param));
+                if ui.button("Cancel").clicked() {
+                    midi.learn = None;
+                }
+            });
+        }
+
+        let mut unmap = None;
+        egui::Grid::new("midi mappings").num_columns(3).striped(true).show(ui, |ui| {
+            for mapping in &midi.mappings {
+                ui.label(mapping.control.label());
+                ui.label(&mapping.param);
+                if ui.button("Remove").clicked() {
+                    unmap = Some(mapping.param.clone());
+                }
+                ui.end_row();
+            }
+        });
+        if let Some(param) = unmap {
+            midi.unmap(&param);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Resource)]
+    struct Knob(f32);
+
+    /// World with the MIDI systems and one param at `test/knob`
+    fn setup(knob: f32) -> (World, Schedule) {
+        let mut world = World::new();
+        world.init_resource::<Events<MidiEvent>>();
+        world.init_resource::<MidiQueue>();
+        world.init_resource::<MidiPorts>();
+        world.insert_resource(MidiMappings { mappings: vec![], learn: None, path: None });
+        world.insert_resource(Knob(knob));
+        world.insert_resource(OscControls {
+            params: vec![OscParam::resource::<Knob>("test/knob", "Test param", 0. ..=1., |k| k.0, |k, v| k.0 = v)],
+            actions: vec![],
+        });
+
+        let mut schedule = Schedule::new();
+        schedule.add_system(midi_receive_system);
+        schedule.add_system(midi_mapping_system.after(midi_receive_system));
+        (world, schedule)
+    }
+
+    /// Pushes raw bytes like the port callback and runs a frame
+    fn receive(world: &mut World, schedule: &mut Schedule, bytes: &[u8]) {
+        let message = MidiMessage::parse(bytes).unwrap();
+        world.resource::<MidiQueue>().push("test", message);
+        schedule.run(world);
+    }
+
+    fn knob(world: &World) -> f32 {
+        world.resource::<Knob>().0
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(MidiMessage::parse(&[0xb1, 7, 100]), Some(MidiMessage::ControlChange { channel: 1, controller: 7, value: 100 }));
+        assert_eq!(MidiMessage::parse(&[0x92, 60, 64]), Some(MidiMessage::NoteOn { channel: 2, note: 60, velocity: 64 }));
+        // Note on without velocity is a note off
+        assert_eq!(MidiMessage::parse(&[0x92, 60, 0]), Some(MidiMessage::NoteOff { channel: 2, note: 60 }));
+        assert_eq!(MidiMessage::parse(&[0xf8]), Some(MidiMessage::Clock));
+        assert_eq!(MidiMessage::parse(&[0xb0, 7]), None);
+        assert_eq!(MidiMessage::parse(&[]), None);
+    }
+
+    #[test]
+    fn last_message_skips_clock() {
+        let (mut world, mut schedule) = setup(0.5);
+        receive(&mut world, &mut schedule, &[0x90, 36, 100]);
+        receive(&mut world, &mut schedule, &[0xf8]);
+        let last = world.resource::<MidiPorts>().last_message.clone().unwrap();
+        assert_eq!(last.port, "test");
+        assert_eq!(last.message, MidiMessage::NoteOn { channel: 0, note: 36, velocity: 100 });
+    }
+
+    #[test]
+    fn learn_then_map() {
+        let (mut world, mut schedule) = setup(0.5);
+        world.resource_mut::<MidiMappings>().learn = Some("test/knob".to_owned());
+
+        // The first move only learns the control
+        receive(&mut world, &mut schedule, &[0xb0, 1, 0]);
+        let midi = world.resource::<MidiMappings>();
+        assert_eq!(midi.learn, None);
+        assert_eq!(midi.mappings.len(), 1);
+        assert_eq!(midi.mappings[0].control, MidiControl::CC { channel: 0, controller: 1 });
+        assert_eq!(knob(&world), 0.5);
+
+        // The learned control takes over right away, even far from the current value
+        receive(&mut world, &mut schedule, &[0xb0, 1, 127]);
+        assert_eq!(knob(&world), 1.);
+        // Other controls don't
+        receive(&mut world, &mut schedule, &[0xb0, 2, 0]);
+        assert_eq!(knob(&world), 1.);
+    }
+
+    #[test]
+    fn soft_takeover() {
+        let (mut world, mut schedule) = setup(0.5);
+        world.resource_mut::<MidiMappings>().map(MidiControl::CC { channel: 0, controller: 1 }, "test/knob".to_owned(), Some(0.5));
+        receive(&mut world, &mut schedule, &[0xb0, 1, 127]);
+        assert_eq!(knob(&world), 1.);
+
+        // Moved by something else, the control has to pick the value up again
+        world.resource_mut::<Knob>().0 = 0.2;
+        receive(&mut world, &mut schedule, &[0xb0, 1, 100]);
+        receive(&mut world, &mut schedule, &[0xb0, 1, 64]);
+        assert_eq!(knob(&world), 0.2);
+
+        // Crossing the value picks it up
+        receive(&mut world, &mut schedule, &[0xb0, 1, 20]);
+        assert_eq!(knob(&world), 20. / 127.);
+        receive(&mut world, &mut schedule, &[0xb0, 1, 127]);
+        assert_eq!(knob(&world), 1.);
+
+        // Within the threshold picks it up without crossing
+        world.resource_mut::<Knob>().0 = 0.5;
+        receive(&mut world, &mut schedule, &[0xb0, 1, 65]);
+        assert_eq!(knob(&world), 65. / 127.);
+    }
+}
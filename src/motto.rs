@@ -9,7 +9,7 @@ use bevy::render::texture::ImageSampler;
 use bevy::sprite::Anchor;
 use crate::{RenderLayers, ShaderRef};
 use crate::AlphaMode::Blend;
-use crate::chipspin::ChipSpinTexture;
+use crate::shared_textures::SharedTextures;
 use crate::shape::Quad;
 
 
@@ -63,7 +63,7 @@ fn setup(
     mut materials: ResMut<Assets<StandardMaterial>>,
     mut chip_card_materials: ResMut<Assets<ChipCardMaterial>>,
     assets: Res<AssetServer>,
-    chip_spin_texture: Res<ChipSpinTexture>,
+    shared_textures: Res<SharedTextures>,
 ) {
     // 2D realm ---------
 
@@ -105,7 +105,7 @@ fn setup(
     commands.spawn(MaterialMeshBundle {
         mesh: meshes.add(shape::Quad::new(Vec2::splat(1.)).into()),
         material: chip_card_materials.add(ChipCardMaterial{
-            chip: chip_spin_texture.texture.clone(),
+            chip: shared_textures.chip_spin.clone(),
             glitch_offset: 0.,
             glitch_pixelation: 0.,
             glitch_abberration: 0.,
@@ -119,7 +119,7 @@ fn setup(
     commands.spawn(MaterialMeshBundle {
         mesh: meshes.add(shape::Quad::new(Vec2::splat(1.)).into()),
         material: chip_card_materials.add(ChipCardMaterial{
-            chip: chip_spin_texture.texture.clone(),
+            chip: shared_textures.chip_spin.clone(),
             glitch_offset: 0.5,
             glitch_pixelation: 0.,
             glitch_abberration: 0.,
@@ -133,7 +133,7 @@ fn setup(
     commands.spawn(MaterialMeshBundle {
         mesh: meshes.add(shape::Quad::new(Vec2::splat(1.)).into()),
         material: chip_card_materials.add(ChipCardMaterial{
-            chip: chip_spin_texture.texture.clone(),
+            chip: shared_textures.chip_spin.clone(),
             glitch_offset: 1.,
             glitch_pixelation: 0.,
             glitch_abberration: 0.,
@@ -0,0 +1,174 @@
+use std::io;
+use std::net::{SocketAddr, UdpSocket};
+use bevy::ecs::event::ManualEventReader;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use bevy_pyree::beat::BeatEvent;
+use rosc::{OscMessage, OscPacket, OscType};
+use crate::beat_controls::{bar_tracker_system, BarTracker};
+use crate::config::AppConfig;
+use crate::osc::{AddOscControls, OscAction, OscControls, OSC_PREFIX};
+use crate::status::StatusPanel;
+
+/// Sends parameter changes, beats and bars to the control surfaces in `osc.feedback_targets`
+pub struct OscFeedbackPlugin;
+
+impl Plugin for OscFeedbackPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_startup_system(setup_osc_feedback)
+            .add_system(osc_feedback_system.after(bar_tracker_system))
+            .add_osc_actions(vec![OscAction::new(
+                "sync", "Resend all parameter values to the feedback targets", "",
+                |world, _| {
+                    if let Some(mut state) = world.get_resource_mut::<OscFeedbackState>() {
+                        state.last_sent.clear();
+                    }
+                    Ok(())
+                },
+            )])
+        ;
+    }
+}
+
+#[derive(Resource)]
+pub struct OscSender {
+    socket: UdpSocket,
+    pub targets: Vec<SocketAddr>,
+}
+
+impl OscSender {
+    pub fn new(targets: Vec<SocketAddr>) -> io::Result<Self> {
+        let socket = UdpSocket::bind("0.0.0.0:0")?;
+        socket.set_nonblocking(true)?;
+        Ok(Self { socket, targets })
+    }
+
+    /// Sends to each of `targets`, one failing doesn't keep the others from getting the message
+    pub fn send(&self, msg: OscMessage, targets: &[SocketAddr]) -> SendReport {
+        let mut report = SendReport::default();
+        let buf = match rosc::encoder::encode(&OscPacket::Message(msg)) {
+            Ok(buf) => buf,
+            Err(e) => {
+                report.errors.push(format!("{:?}", e));
+                return report;
+            }
+        };
+        for target in targets {
+            match self.socket.send_to(&buf, target) {
+                Ok(_) => report.delivered.push(*target),
+                // The socket buffer is full, the message can be sent again later
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => {}
+                Err(e) => report.errors.push(format!("sending to {} failed: {}", target, e)),
+            }
+        }
+        report
+    }
+}
+
+/// Outcome of sending one message
+#[derive(Default)]
+pub struct SendReport {
+    pub delivered: Vec<SocketAddr>,
+    /// One entry per failed target
+    pub errors: Vec<String>,
+}
+
+#[derive(Resource)]
+pub struct OscFeedbackState {
+    /// Paces parameter updates, beats are sent right away
+    pub timer: Timer,
+    /// Last value delivered per target and param path, so unchanged values aren't resent
+    pub last_sent: HashMap<SocketAddr, HashMap<String, f32>>,
+}
+
+fn setup_osc_feedback(
+    mut commands: Commands,
+    config: Res<AppConfig>,
+    mut status: ResMut<StatusPanel>,
+) {
+    // Validated when loading the config
+    let targets = config.osc_feedback_targets().unwrap_or_default();
+    if targets.is_empty() { return; }
+
+    match OscSender::new(targets) {
+        Ok(sender) => {
+            commands.insert_resource(sender);
+            commands.insert_resource(OscFeedbackState {
+                timer: Timer::from_seconds(1. / config.osc.feedback_rate, TimerMode::Repeating),
+                last_sent: HashMap::default(),
+            });
+        }
+        Err(e) => status.error("osc_feedback", format!("Failed to open OSC feedback socket: {}", e)),
+    }
+}
+
+fn osc_feedback_system(
+    world: &mut World,
+    mut beat_reader: Local<ManualEventReader<BeatEvent>>,
+) {
+    let beats = beat_reader.iter(world.resource::<Events<BeatEvent>>()).count();
+    if !world.contains_resource::<OscSender>() { return; }
+
+    let mut messages = vec![];
+    if beats > 0 {
+        let bar = world.resource::<BarTracker>();
+        messages.push(OscMessage {
+            addr: format!("{}/beat", OSC_PREFIX),
+            args: vec![OscType::Int(bar.beat_in_bar() as i32 + 1)],
+        });
+        if bar.beat_in_bar() == 0 {
+            messages.push(OscMessage {
+                addr: format!("{}/bar", OSC_PREFIX),
+                args: vec![OscType::Int(bar.bar() as i32 + 1)],
+            });
+        }
+    }
+    let delta = world.resource::<Time>().delta();
+    let mut state = world.resource_mut::<OscFeedbackState>();
+    let update_params = state.timer.tick(delta).just_finished();
+
+    let targets = world.resource::<OscSender>().targets.clone();
+    let mut changed = vec![];
+    if update_params {
+        let params = world.resource::<OscControls>().params.clone();
+        for param in params {
+            let Some(value) = param.get(world) else { continue; };
+            let last_sent = &world.resource::<OscFeedbackState>().last_sent;
+            let stale: Vec<SocketAddr> = targets.iter()
+                .filter(|t| last_sent.get(*t).and_then(|sent| sent.get(&param.path)) != Some(&value))
+                .copied()
+                .collect();
+            if stale.is_empty() { continue; }
+            let msg = OscMessage { addr: param.address(), args: vec![param.osc_arg(value)] };
+            changed.push((param.path, value, msg, stale));
+        }
+    }
+
+    if messages.is_empty() && changed.is_empty() { return; }
+    let sender = world.resource::<OscSender>();
+    let mut errors = vec![];
+    // Beats are stale by the next frame, they aren't retried
+    for msg in messages {
+        errors.extend(sender.send(msg, &targets).errors);
+    }
+    let mut delivered = vec![];
+    for (path, value, msg, stale) in changed {
+        let report = sender.send(msg, &stale);
+        // Targets that didn't get it still see the value as changed and get it with the next update
+        delivered.extend(report.delivered.into_iter().map(|target| (target, path.clone(), value)));
+        errors.extend(report.errors);
+    }
+    let mut state = world.resource_mut::<OscFeedbackState>();
+    for (target, path, value) in delivered {
+        state.last_sent.entry(target).or_default().insert(path, value);
+    }
+
+    errors.sort();
+    errors.dedup();
+    let mut status = world.resource_mut::<StatusPanel>();
+    match errors.is_empty() {
+        true => status.resolve("osc_feedback"),
+        false => status.warn("osc_feedback", format!("OSC feedback: {}", errors.join(", "))),
+    }
+}
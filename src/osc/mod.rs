@@ -0,0 +1,377 @@
+pub mod feedback;
+pub mod monitor;
+pub mod query;
+pub mod routing;
+pub mod schema;
+pub mod server;
+
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+use bevy::prelude::*;
+use bevy_rosc::MultiAddressOscMethod;
+use rosc::{OscMessage, OscType};
+use serde::Deserialize;
+use crate::osc::schema::{ArgSchema, ArgSpec, OscSchemas};
+use crate::status::StatusPanel;
+
+/// Prefix of every address in the control namespace
+pub const OSC_PREFIX: &str = "/vj";
+
+pub struct OscControlPlugin;
+
+impl Plugin for OscControlPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<OscControls>()
+            .init_resource::<OscSchemas>()
+            .add_startup_system(spawn_osc_control)
+            .add_system(osc_control_system)
+        ;
+    }
+}
+
+/// Type an argument gets converted to
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ArgType {
+    Int,
+    Float,
+    Bool,
+}
+
+impl ArgType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ArgType::Int => "int",
+            ArgType::Float => "float",
+            ArgType::Bool => "bool",
+        }
+    }
+
+    pub fn convert(&self, value: f32) -> OscType {
+        match self {
+            ArgType::Int => OscType::Int(value.round() as i32),
+            ArgType::Float => OscType::Float(value),
+            ArgType::Bool => OscType::Bool(value >= 0.5),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ParamKind {
+    Float,
+    Int,
+    Bool,
+}
+
+type Getter = Arc<dyn Fn(&mut World) -> Option<f32> + Send + Sync>;
+type Setter = Arc<dyn Fn(&mut World, f32) + Send + Sync>;
+type RangeGetter = Arc<dyn Fn(&World) -> Option<RangeInclusive<f32>> + Send + Sync>;
+type Runner = Arc<dyn Fn(&mut World, &[OscType]) -> Result<(), String> + Send + Sync>;
+
+/// A value that can be read and written through `/vj/<path> <value>`.
+/// Values are passed around as `f32` regardless of kind, bools as 0 or 1.
+#[derive(Clone)]
+pub struct OscParam {
+    pub path: String,
+    pub description: String,
+    pub kind: ParamKind,
+    pub range: RangeInclusive<f32>,
+    /// Replaces `range` for bounds that change at runtime
+    current_range: Option<RangeGetter>,
+    get: Getter,
+    set: Setter,
+}
+
+impl OscParam {
+    pub fn new(
+        path: impl Into<String>,
+        description: impl Into<String>,
+        kind: ParamKind,
+        range: RangeInclusive<f32>,
+        get: impl Fn(&mut World) -> Option<f32> + Send + Sync + 'static,
+        set: impl Fn(&mut World, f32) + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            path: path.into(),
+            description: description.into(),
+            kind,
+            range,
+            current_range: None,
+            get: Arc::new(get),
+            set: Arc::new(set),
+        }
+    }
+
+    /// Reads the range from the world whenever the value is set, falling back to `range` if it returns `None`
+    pub fn with_current_range(self, range: impl Fn(&World) -> Option<RangeInclusive<f32>> + Send + Sync + 'static) -> Self {
+        Self { current_range: Some(Arc::new(range)), ..self }
+    }
+
+    pub fn current_range(&self, world: &World) -> RangeInclusive<f32> {
+        self.current_range.as_ref()
+            .and_then(|range| range(world))
+            .unwrap_or_else(|| self.range.clone())
+    }
+
+    /// Float field of the single instance of material `M`
+    pub fn material<M: Material>(
+        path: impl Into<String>,
+        description: impl Into<String>,
+        range: RangeInclusive<f32>,
+        get: impl Fn(&M) -> f32 + Send + Sync + 'static,
+        set: impl Fn(&mut M, f32) + Send + Sync + 'static,
+    ) -> Self {
+        Self::new(path, description, ParamKind::Float, range,
+            move |world| material::<M>(world).map(&get),
+            move |world, v| if let Some(mat) = material_mut::<M>(world) { set(mat, v) },
+        )
+    }
+
+    /// Float field of resource `R`
+    pub fn resource<R: Resource>(
+        path: impl Into<String>,
+        description: impl Into<String>,
+        range: RangeInclusive<f32>,
+        get: impl Fn(&R) -> f32 + Send + Sync + 'static,
+        set: impl Fn(&mut R, f32) + Send + Sync + 'static,
+    ) -> Self {
+        Self::new(path, description, ParamKind::Float, range,
+            move |world| world.get_resource::<R>().map(&get),
+            move |world, v| if let Some(mut res) = world.get_resource_mut::<R>() { set(&mut *res, v) },
+        )
+    }
+
+    /// Integer field of resource `R`
+    pub fn resource_int<R: Resource>(
+        path: impl Into<String>,
+        description: impl Into<String>,
+        range: RangeInclusive<i32>,
+        get: impl Fn(&R) -> i32 + Send + Sync + 'static,
+        set: impl Fn(&mut R, i32) + Send + Sync + 'static,
+    ) -> Self {
+        let range = *range.start() as f32..=*range.end() as f32;
+        Self::new(path, description, ParamKind::Int, range,
+            move |world| world.get_resource::<R>().map(|r| get(r) as f32),
+            move |world, v| if let Some(mut res) = world.get_resource_mut::<R>() { set(&mut *res, v as i32) },
+        )
+    }
+
+    /// Bool field of resource `R`
+    pub fn resource_bool<R: Resource>(
+        path: impl Into<String>,
+        description: impl Into<String>,
+        get: impl Fn(&R) -> bool + Send + Sync + 'static,
+        set: impl Fn(&mut R, bool) + Send + Sync + 'static,
+    ) -> Self {
+        Self::new(path, description, ParamKind::Bool, 0. ..=1.,
+            move |world| world.get_resource::<R>().map(|r| if get(r) { 1. } else { 0. }),
+            move |world, v| if let Some(mut res) = world.get_resource_mut::<R>() { set(&mut *res, v >= 0.5) },
+        )
+    }
+
+    pub fn address(&self) -> String {
+        format!("{}/{}", OSC_PREFIX, self.path)
+    }
+
+    pub fn schema(&self) -> ArgSchema {
+        let spec = match self.kind {
+            // `set` clamps to the range at the time
+            ParamKind::Float if self.current_range.is_some() => ArgSpec::float_unclamped("value"),
+            ParamKind::Float => ArgSpec::float("value", self.range.clone()),
+            ParamKind::Int => ArgSpec::int("value", *self.range.start() as i32..=*self.range.end() as i32),
+            ParamKind::Bool => ArgSpec::bool("value"),
+        };
+        ArgSchema::new(vec![spec])
+    }
+
+    /// The value as sent to control surfaces
+    pub fn osc_arg(&self, value: f32) -> OscType {
+        match self.kind {
+            ParamKind::Float => OscType::Float(value),
+            ParamKind::Int => OscType::Int(value.round() as i32),
+            ParamKind::Bool => OscType::Bool(value >= 0.5),
+        }
+    }
+
+    /// Returns `None` if the module holding the value isn't set up
+    pub fn get(&self, world: &mut World) -> Option<f32> {
+        (self.get)(world)
+    }
+
+    /// Sets the value, clamped to the range and rounded for int and bool params
+    pub fn set(&self, world: &mut World, value: f32) {
+        if !value.is_finite() { return; }
+        let range = self.current_range(world);
+        let value = value.clamp(*range.start(), *range.end());
+        let value = match self.kind {
+            ParamKind::Float => value,
+            ParamKind::Int | ParamKind::Bool => value.round(),
+        };
+        (self.set)(world, value);
+    }
+}
+
+/// Something that happens once per message, like triggering a wipe
+#[derive(Clone)]
+pub struct OscAction {
+    pub path: String,
+    pub description: String,
+    /// OSC type tags of the arguments, e.g. `"si"` for a string and an int
+    pub args: &'static str,
+    /// Checked before the action runs, if set
+    pub schema: Option<ArgSchema>,
+    run: Runner,
+}
+
+impl OscAction {
+    pub fn new(
+        path: impl Into<String>,
+        description: impl Into<String>,
+        args: &'static str,
+        run: impl Fn(&mut World, &[OscType]) -> Result<(), String> + Send + Sync + 'static,
+    ) -> Self {
+        Self { path: path.into(), description: description.into(), args, schema: None, run: Arc::new(run) }
+    }
+
+    pub fn with_schema(self, schema: ArgSchema) -> Self {
+        Self { schema: Some(schema), ..self }
+    }
+
+    pub fn address(&self) -> String {
+        format!("{}/{}", OSC_PREFIX, self.path)
+    }
+
+    pub fn run(&self, world: &mut World, args: &[OscType]) -> Result<(), String> {
+        (self.run)(world, args)
+    }
+}
+
+/// Every parameter and action reachable through OSC. Modules register theirs when they're built.
+#[derive(Resource, Default)]
+pub struct OscControls {
+    pub params: Vec<OscParam>,
+    pub actions: Vec<OscAction>,
+}
+
+impl OscControls {
+    pub fn param(&self, address: &str) -> Option<&OscParam> {
+        let path = address.strip_prefix(OSC_PREFIX)?.strip_prefix('/')?;
+        self.params.iter().find(|p| p.path == path)
+    }
+
+    pub fn action(&self, address: &str) -> Option<&OscAction> {
+        let path = address.strip_prefix(OSC_PREFIX)?.strip_prefix('/')?;
+        self.actions.iter().find(|a| a.path == path)
+    }
+}
+
+pub trait AddOscControls {
+    fn add_osc_params(&mut self, params: Vec<OscParam>) -> &mut Self;
+    fn add_osc_actions(&mut self, actions: Vec<OscAction>) -> &mut Self;
+}
+
+impl AddOscControls for App {
+    fn add_osc_params(&mut self, params: Vec<OscParam>) -> &mut Self {
+        self.init_resource::<OscControls>();
+        self.world.resource_mut::<OscControls>().params.extend(params);
+        self
+    }
+
+    fn add_osc_actions(&mut self, actions: Vec<OscAction>) -> &mut Self {
+        self.init_resource::<OscControls>();
+        self.world.resource_mut::<OscControls>().actions.extend(actions);
+        self
+    }
+}
+
+/// Marks an entity rendering a copy of a module's material, kept in sync by the module.
+/// Ignored when looking up the single instance.
+#[derive(Component)]
+pub struct MaterialCopy;
+
+/// The single instance of material `M`, as used by the fullscreen shader modules
+pub fn material<M: Material>(world: &mut World) -> Option<&M> {
+    let handle = world.query_filtered::<&Handle<M>, Without<MaterialCopy>>().get_single(world).ok()?.clone();
+    world.resource::<Assets<M>>().get(&handle)
+}
+
+pub fn material_mut<M: Material>(world: &mut World) -> Option<&mut M> {
+    let handle = world.query_filtered::<&Handle<M>, Without<MaterialCopy>>().get_single(world).ok()?.clone();
+    world.resource_mut::<Assets<M>>().into_inner().get_mut(&handle)
+}
+
+/// Numeric value of an argument. Bools are accepted so toggle buttons can drive params.
+pub fn arg_f32(arg: &OscType) -> Option<f32> {
+    match arg {
+        OscType::Float(f) => Some(*f),
+        OscType::Double(d) => Some(*d as f32),
+        OscType::Int(i) => Some(*i as f32),
+        OscType::Long(l) => Some(*l as f32),
+        OscType::Bool(b) => Some(if *b { 1. } else { 0. }),
+        _ => None,
+    }
+}
+
+pub fn arg_int(arg: &OscType) -> Option<i32> {
+    arg_f32(arg).map(|f| f.round() as i32)
+}
+
+#[derive(Component)]
+pub struct OscControlReceiver;
+
+fn spawn_osc_control(
+    mut commands: Commands,
+    controls: Res<OscControls>,
+    mut schemas: ResMut<OscSchemas>,
+) {
+    for param in &controls.params {
+        schemas.schemas.insert(param.address(), param.schema());
+    }
+    for action in &controls.actions {
+        if let Some(schema) = &action.schema {
+            schemas.schemas.insert(action.address(), schema.clone());
+        }
+    }
+
+    let addresses = controls.params.iter().map(|p| p.address())
+        .chain(controls.actions.iter().map(|a| a.address()))
+        .collect();
+    commands.spawn((
+        MultiAddressOscMethod::new(addresses).unwrap(),
+        OscControlReceiver,
+    ));
+}
+
+/// Applies incoming `/vj/...` messages. A param message without argument is ignored.
+fn osc_control_system(world: &mut World) {
+    let mut messages: Vec<OscMessage> = Vec::new();
+    let mut query = world.query_filtered::<&mut MultiAddressOscMethod, With<OscControlReceiver>>();
+    for mut osc in query.iter_mut(world) {
+        while let Some(msg) = osc.get_message() {
+            messages.push(msg);
+        }
+    }
+    if messages.is_empty() { return; }
+
+    world.resource_scope(|world, controls: Mut<OscControls>| {
+        for msg in messages {
+            let result = if let Some(param) = controls.param(&msg.addr) {
+                match msg.args.first() {
+                    Some(arg) => arg_f32(arg)
+                        .map(|value| param.set(world, value))
+                        .ok_or_else(|| format!("expected a number, got {:?}", arg)),
+                    None => Ok(()),
+                }
+            } else if let Some(action) = controls.action(&msg.addr) {
+                action.run(world, &msg.args)
+            } else {
+                Ok(())
+            };
+
+            if let Err(e) = result {
+                world.resource_mut::<StatusPanel>().warn("osc_control", format!("OSC {}: {}", msg.addr, e));
+            }
+        }
+    });
+}
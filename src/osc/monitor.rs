@@ -0,0 +1,188 @@
+use std::collections::VecDeque;
+use std::net::SocketAddr;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use bevy_egui::{egui, EguiContexts};
+use rosc::{OscMessage, OscType};
+use crate::osc::schema::OscSchemas;
+use crate::osc::server::OscServer;
+
+const HISTORY_LEN: usize = 200;
+const RATE_WINDOW: f64 = 1.;
+
+pub struct OscMonitorPlugin;
+
+impl Plugin for OscMonitorPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<OscMonitor>()
+            .add_system(osc_monitor_rate_system)
+            .add_system(osc_monitor_ui)
+        ;
+    }
+}
+
+pub struct MonitorEntry {
+    pub time: f64,
+    pub source: SocketAddr,
+    pub addr: String,
+    pub args: String,
+}
+
+#[derive(Default)]
+pub struct AddressStats {
+    pub count: u64,
+    pub last_seen: f64,
+    /// Messages per second, measured over the last `RATE_WINDOW`
+    pub rate: f32,
+    window_count: u32,
+}
+
+#[derive(Default)]
+pub struct SourceStats {
+    pub count: u64,
+    pub last_seen: f64,
+}
+
+/// Every received OSC message, recorded by the server before routing and scheduling
+#[derive(Resource, Default)]
+pub struct OscMonitor {
+    pub history: VecDeque<MonitorEntry>,
+    pub addresses: HashMap<String, AddressStats>,
+    pub sources: HashMap<SocketAddr, SourceStats>,
+    /// Stops adding to the history, stats keep updating
+    pub paused: bool,
+    pub filter: String,
+    window_start: f64,
+}
+
+impl OscMonitor {
+    pub fn record(&mut self, msg: &OscMessage, source: SocketAddr, time: f64) {
+        let address = self.addresses.entry(msg.addr.clone()).or_default();
+        address.count += 1;
+        address.window_count += 1;
+        address.last_seen = time;
+
+        let source_stats = self.sources.entry(source).or_default();
+        source_stats.count += 1;
+        source_stats.last_seen = time;
+
+        if self.paused { return; }
+        if self.history.len() >= HISTORY_LEN {
+            self.history.pop_front();
+        }
+        self.history.push_back(MonitorEntry { time, source, addr: msg.addr.clone(), args: format_args(&msg.args) });
+    }
+}
+
+pub fn format_args(args: &[OscType]) -> String {
+    args.iter()
+        .map(|arg| match arg {
+            OscType::Int(i) => i.to_string(),
+            OscType::Long(l) => l.to_string(),
+            OscType::Float(f) => format!("{:.3}", f),
+            OscType::Double(d) => format!("{:.3}", d),
+            OscType::String(s) => format!("\"{}\"", s),
+            OscType::Bool(b) => b.to_string(),
+            other => format!("{:?}", other),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn osc_monitor_rate_system(
+    mut monitor: ResMut<OscMonitor>,
+    time: Res<Time>,
+) {
+    let now = time.elapsed_seconds_f64();
+    let elapsed = now - monitor.window_start;
+    if elapsed < RATE_WINDOW { return; }
+    monitor.window_start = now;
+    for stats in monitor.addresses.values_mut() {
+        stats.rate = (stats.window_count as f64 / elapsed) as f32;
+        stats.window_count = 0;
+    }
+}
+
+fn osc_monitor_ui(
+    mut contexts: EguiContexts,
+    mut monitor: ResMut<OscMonitor>,
+    server: Res<OscServer>,
+    mut schemas: ResMut<OscSchemas>,
+    time: Res<Time>,
+) {
+    let now = time.elapsed_seconds_f64();
+    let monitor = &mut *monitor;
+
+    egui::Window::new("OSC Monitor").default_open(false).show(contexts.ctx_mut(), |ui| {
+        ui.horizontal(|ui| {
+            ui.label("Filter");
+            ui.text_edit_singleline(&mut monitor.filter);
+            ui.checkbox(&mut monitor.paused, "Pause");
+            if ui.button("Clear").clicked() {
+                monitor.history.clear();
+                monitor.addresses.clear();
+                monitor.sources.clear();
+            }
+        });
+        ui.label(format!("{} scheduled bundle messages pending", server.scheduled()));
+
+        ui.separator();
+        ui.label("Sources");
+        if monitor.sources.is_empty() {
+            ui.label("Nothing received yet");
+        }
+        egui::Grid::new("osc sources").num_columns(3).striped(true).show(ui, |ui| {
+            let mut sources: Vec<_> = monitor.sources.iter().collect();
+            sources.sort_by_key(|(addr, _)| **addr);
+            for (addr, stats) in sources {
+                ui.label(addr.to_string());
+                ui.label(format!("{} msgs", stats.count));
+                ui.label(format!("{:.1}s ago", now - stats.last_seen));
+                ui.end_row();
+            }
+        });
+
+        ui.separator();
+        ui.label("Addresses");
+        egui::Grid::new("osc addresses").num_columns(4).striped(true).show(ui, |ui| {
+            let mut addresses: Vec<_> = monitor.addresses.iter()
+                .filter(|(addr, _)| addr.contains(monitor.filter.as_str()))
+                .collect();
+            addresses.sort_by(|a, b| a.0.cmp(b.0));
+            for (addr, stats) in addresses {
+                ui.label(addr);
+                ui.label(format!("{:.1}/s", stats.rate));
+                ui.label(format!("{} msgs", stats.count));
+                ui.label(format!("{:.1}s ago", now - stats.last_seen));
+                ui.end_row();
+            }
+        });
+
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.label("Rejected arguments");
+            if ui.button("Reset").clicked() {
+                schemas.errors.clear();
+            }
+        });
+        egui::Grid::new("osc arg errors").num_columns(3).striped(true).show(ui, |ui| {
+            let mut errors: Vec<_> = schemas.errors.iter().collect();
+            errors.sort_by(|a, b| a.0.cmp(b.0));
+            for (addr, errors) in errors {
+                ui.label(addr);
+                ui.label(format!("{}x", errors.count));
+                ui.label(&errors.last_error);
+                ui.end_row();
+            }
+        });
+
+        ui.separator();
+        ui.label("Recent messages");
+        egui::ScrollArea::vertical().max_height(300.).stick_to_bottom(true).show(ui, |ui| {
+            for entry in monitor.history.iter().filter(|e| e.addr.contains(monitor.filter.as_str())) {
+                ui.monospace(format!("{:>9.3} {:<21} {} {}", entry.time, entry.source, entry.addr, entry.args));
+            }
+        });
+    });
+}
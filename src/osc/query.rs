@@ -0,0 +1,188 @@
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, RwLock};
+use std::thread;
+use std::time::Duration;
+use bevy::prelude::*;
+use serde_json::{json, Map, Value};
+use crate::config::AppConfig;
+use crate::osc::{OscControls, ParamKind};
+use crate::status::StatusPanel;
+
+const REFRESH_INTERVAL: f32 = 0.25;
+
+/// Serves an OSCQuery namespace description of all `/vj` params and actions on
+/// `http://localhost:<osc.query_port>/`, if the port is configured
+pub struct OscQueryPlugin;
+
+impl Plugin for OscQueryPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_startup_system(start_osc_query_server)
+            .add_system(osc_query_refresh_system)
+        ;
+    }
+}
+
+/// Namespace tree shared with the server thread, rebuilt a few times a second with current values
+#[derive(Resource)]
+pub struct OscQueryTree {
+    pub tree: Arc<RwLock<Value>>,
+    pub timer: Timer,
+}
+
+fn container(full_path: &str) -> Value {
+    json!({ "FULL_PATH": full_path, "ACCESS": 0, "CONTENTS": {} })
+}
+
+fn insert_node(root: &mut Value, address: &str, node: Value) {
+    let mut current = root;
+    let mut full_path = String::new();
+    for part in address.trim_start_matches('/').split('/') {
+        full_path.push('/');
+        full_path.push_str(part);
+        if current["CONTENTS"].get(part).is_none() {
+            current["CONTENTS"][part] = container(&full_path);
+        }
+        current = &mut current["CONTENTS"][part];
+    }
+    *current = node;
+}
+
+fn build_tree(world: &mut World) -> Value {
+    let mut root = container("/");
+    let params = world.resource::<OscControls>().params.clone();
+    for param in params {
+        // Bools are always advertised as "T", the state is in VALUE
+        let type_tag = match param.kind {
+            ParamKind::Float => "f",
+            ParamKind::Int => "i",
+            ParamKind::Bool => "T",
+        };
+        let mut node = json!({
+            "FULL_PATH": param.address(),
+            "TYPE": type_tag,
+            "ACCESS": 3,
+            "DESCRIPTION": param.description,
+        });
+        if param.kind != ParamKind::Bool {
+            let range = param.current_range(world);
+            node["RANGE"] = json!([{ "MIN": range.start(), "MAX": range.end() }]);
+        }
+        if let Some(value) = param.get(world) {
+            node["VALUE"] = match param.kind {
+                ParamKind::Float => json!([value]),
+                ParamKind::Int => json!([value.round() as i32]),
+                ParamKind::Bool => json!([value >= 0.5]),
+            };
+        }
+        insert_node(&mut root, &param.address(), node);
+    }
+
+    for action in world.resource::<OscControls>().actions.iter() {
+        insert_node(&mut root, &action.address(), json!({
+            "FULL_PATH": action.address(),
+            "TYPE": action.args,
+            "ACCESS": 2,
+            "DESCRIPTION": action.description,
+        }));
+    }
+    root
+}
+
+fn start_osc_query_server(
+    mut commands: Commands,
+    config: Res<AppConfig>,
+    mut status: ResMut<StatusPanel>,
+) {
+    let Some(port) = config.osc.query_port else { return; };
+    let listener = match TcpListener::bind(("127.0.0.1", port)) {
+        Ok(listener) => listener,
+        Err(e) => {
+            status.error("osc_query", format!("Failed to start OSCQuery server on port {}: {}", port, e));
+            return;
+        }
+    };
+
+    let host_info = json!({
+        "NAME": config.window.title,
+        "OSC_PORT": config.osc_addr().map(|a| a.port()).unwrap_or_default(),
+        "OSC_TRANSPORT": "UDP",
+        "EXTENSIONS": {
+            "ACCESS": true, "VALUE": true, "RANGE": true, "DESCRIPTION": true,
+            "TYPE": true, "FULL_PATH": true, "CONTENTS": true,
+        },
+    });
+    let tree = Arc::new(RwLock::new(container("/")));
+    let server_tree = tree.clone();
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            if let Err(e) = handle_request(stream, &server_tree, &host_info) {
+                warn!("OSCQuery request failed: {}", e);
+            }
+        }
+    });
+    info!("OSCQuery server listening on http://127.0.0.1:{}/", port);
+
+    commands.insert_resource(OscQueryTree {
+        tree,
+        timer: Timer::from_seconds(REFRESH_INTERVAL, TimerMode::Repeating),
+    });
+}
+
+fn osc_query_refresh_system(world: &mut World) {
+    let delta = world.resource::<Time>().delta();
+    let Some(mut query_tree) = world.get_resource_mut::<OscQueryTree>() else { return; };
+    if !query_tree.timer.tick(delta).just_finished() { return; }
+    let shared = query_tree.tree.clone();
+
+    let tree = build_tree(world);
+    if let Ok(mut shared) = shared.write() {
+        *shared = tree;
+    }
+}
+
+/// Answers `GET /vj/some/path`, optionally with a single attribute like `?VALUE` or `?HOST_INFO`
+fn handle_request(stream: TcpStream, tree: &RwLock<Value>, host_info: &Value) -> io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(2)))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() { break; }
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let response = match (parts.next(), parts.next()) {
+        (Some("GET"), Some(target)) => {
+            let (path, attribute) = target.split_once('?').unwrap_or((target, ""));
+            let tree = tree.read().map_err(|_| io::Error::new(io::ErrorKind::Other, "tree lock poisoned"))?;
+            let node = path.trim_end_matches('/').split('/').skip(1)
+                .try_fold(&*tree, |node, part| node.get("CONTENTS")?.get(part));
+            match (node, attribute) {
+                (_, "HOST_INFO") => Some(host_info.clone()),
+                (Some(node), "") => Some(node.clone()),
+                (Some(node), attribute) => node.get(attribute).map(|v| {
+                    let mut map = Map::new();
+                    map.insert(attribute.to_owned(), v.clone());
+                    Value::Object(map)
+                }),
+                (None, _) => None,
+            }
+        }
+        _ => None,
+    };
+
+    let (status, body) = match response {
+        Some(body) => ("200 OK", body.to_string()),
+        None => ("404 Not Found", String::new()),
+    };
+    let mut stream = stream;
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status, body.len(), body,
+    )?;
+    stream.flush()
+}
@@ -0,0 +1,114 @@
+use rosc::{OscMessage, OscType};
+use serde::Deserialize;
+use crate::osc::{arg_f32, ArgType};
+use crate::status::StatusPanel;
+
+/// Maps an incoming address onto an internal one, e.g. `/mixxx/beat` onto `/beat`
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OscRoute {
+    pub from: String,
+    pub to: String,
+    /// Index of the incoming argument passed on
+    #[serde(default)]
+    pub arg: usize,
+    /// `[in_min, in_max, out_min, out_max]`, maps the argument linearly
+    pub scale: Option<[f32; 4]>,
+    /// Type the argument is converted to, float if scaled and not given
+    #[serde(rename = "type")]
+    pub arg_type: Option<ArgType>,
+    /// Also dispatch the original message
+    #[serde(default)]
+    pub passthrough: bool,
+}
+
+impl OscRoute {
+    pub fn apply(&self, msg: &OscMessage) -> Result<OscMessage, String> {
+        let converts = self.scale.is_some() || self.arg_type.is_some();
+        let args = match msg.args.get(self.arg) {
+            // Triggers like beats usually come without arguments
+            None if !converts => vec![],
+            None => return Err(format!("missing argument {}", self.arg)),
+            Some(arg) if !converts => vec![arg.clone()],
+            Some(arg) => vec![self.convert(arg)?],
+        };
+        Ok(OscMessage { addr: self.to.clone(), args })
+    }
+
+    fn convert(&self, arg: &OscType) -> Result<OscType, String> {
+        let mut value = arg_f32(arg).ok_or_else(|| format!("argument {} is not a number: {:?}", self.arg, arg))?;
+        if let Some([in_min, in_max, out_min, out_max]) = self.scale {
+            value = out_min + (value - in_min) / (in_max - in_min) * (out_max - out_min);
+        }
+        Ok(self.arg_type.unwrap_or(ArgType::Float).convert(value))
+    }
+}
+
+/// Applies all routes matching the message's address. Messages without a route are passed on unchanged.
+pub fn route(routes: &[OscRoute], msg: OscMessage, status: &mut StatusPanel) -> Vec<OscMessage> {
+    let matching: Vec<&OscRoute> = routes.iter().filter(|r| r.from == msg.addr).collect();
+    let mut out = vec![];
+    for route in &matching {
+        match route.apply(&msg) {
+            Ok(routed) => out.push(routed),
+            Err(e) => status.warn("osc_route", format!("OSC route {} -> {}: {}", route.from, route.to, e)),
+        }
+    }
+    if matching.is_empty() || matching.iter().any(|r| r.passthrough) {
+        out.push(msg);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn route_to(to: &str) -> OscRoute {
+        OscRoute { from: "/in".into(), to: to.into(), arg: 0, scale: None, arg_type: None, passthrough: false }
+    }
+
+    fn msg(args: Vec<OscType>) -> OscMessage {
+        OscMessage { addr: "/in".into(), args }
+    }
+
+    #[test]
+    fn scale() {
+        let route = OscRoute { scale: Some([0., 127., 0., 1.]), ..route_to("/out") };
+        let routed = route.apply(&msg(vec![OscType::Int(127)])).unwrap();
+        assert_eq!(routed.addr, "/out");
+        assert_eq!(routed.args, vec![OscType::Float(1.)]);
+    }
+
+    #[test]
+    fn arg_type() {
+        let route = OscRoute { arg: 1, arg_type: Some(ArgType::Int), ..route_to("/out") };
+        let routed = route.apply(&msg(vec![OscType::String("x".into()), OscType::Float(2.6)])).unwrap();
+        assert_eq!(routed.args, vec![OscType::Int(3)]);
+        assert!(route.apply(&msg(vec![OscType::Float(1.), OscType::String("x".into())])).is_err());
+        assert!(route.apply(&msg(vec![])).is_err());
+    }
+
+    #[test]
+    fn unconverted_args_pass_unchanged() {
+        let route = route_to("/beat");
+        assert_eq!(route.apply(&msg(vec![OscType::String("x".into())])).unwrap().args, vec![OscType::String("x".into())]);
+        assert_eq!(route.apply(&msg(vec![])).unwrap().args, vec![]);
+    }
+
+    #[test]
+    fn passthrough() {
+        let mut status = StatusPanel::default();
+        let routes = [route_to("/out")];
+        let out = route(&routes, msg(vec![]), &mut status);
+        assert_eq!(out.iter().map(|m| m.addr.as_str()).collect::<Vec<_>>(), ["/out"]);
+
+        let routes = [OscRoute { passthrough: true, ..route_to("/out") }];
+        let out = route(&routes, msg(vec![]), &mut status);
+        assert_eq!(out.iter().map(|m| m.addr.as_str()).collect::<Vec<_>>(), ["/out", "/in"]);
+
+        let out = route(&routes, OscMessage { addr: "/other".into(), args: vec![] }, &mut status);
+        assert_eq!(out.iter().map(|m| m.addr.as_str()).collect::<Vec<_>>(), ["/other"]);
+        assert!(status.active().next().is_none());
+    }
+}
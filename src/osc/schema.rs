@@ -0,0 +1,127 @@
+use std::ops::RangeInclusive;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use rosc::OscType;
+use crate::osc::{arg_f32, ArgType};
+
+/// Expected type and range of one argument
+#[derive(Clone)]
+pub struct ArgSpec {
+    pub name: &'static str,
+    /// `None` passes the argument on unchecked, for handlers that accept several types
+    pub kind: Option<ArgType>,
+    pub range: Option<RangeInclusive<f32>>,
+    pub optional: bool,
+}
+
+impl ArgSpec {
+    pub fn int(name: &'static str, range: RangeInclusive<i32>) -> Self {
+        Self { name, kind: Some(ArgType::Int), range: Some(*range.start() as f32..=*range.end() as f32), optional: false }
+    }
+
+    pub fn float(name: &'static str, range: RangeInclusive<f32>) -> Self {
+        Self { name, kind: Some(ArgType::Float), range: Some(range), optional: false }
+    }
+
+    /// Float left for the handler to clamp, for ranges that change at runtime
+    pub fn float_unclamped(name: &'static str) -> Self {
+        Self { name, kind: Some(ArgType::Float), range: None, optional: false }
+    }
+
+    pub fn bool(name: &'static str) -> Self {
+        Self { name, kind: Some(ArgType::Bool), range: None, optional: false }
+    }
+
+    pub fn any(name: &'static str) -> Self {
+        Self { name, kind: None, range: None, optional: false }
+    }
+
+    /// Optional arguments may only be followed by other optional ones
+    pub fn optional(self) -> Self {
+        Self { optional: true, ..self }
+    }
+}
+
+/// Arguments of an address. Numeric arguments of any type (int, long, float, double, bool)
+/// are converted to the expected type and clamped to the range. Extra arguments are dropped.
+#[derive(Clone, Default)]
+pub struct ArgSchema {
+    pub args: Vec<ArgSpec>,
+}
+
+impl ArgSchema {
+    pub fn new(args: Vec<ArgSpec>) -> Self {
+        Self { args }
+    }
+
+    pub fn apply(&self, args: &[OscType]) -> Result<Vec<OscType>, String> {
+        let mut out = vec![];
+        for (i, spec) in self.args.iter().enumerate() {
+            let Some(arg) = args.get(i) else {
+                if spec.optional { break; }
+                return Err(format!("missing argument {} ({})", i, spec.name));
+            };
+            let Some(kind) = spec.kind else {
+                out.push(arg.clone());
+                continue;
+            };
+            let value = arg_f32(arg)
+                .filter(|v| v.is_finite())
+                .ok_or_else(|| format!("argument {} ({}) must be {}, got {:?}", i, spec.name, kind.name(), arg))?;
+            let value = match &spec.range {
+                Some(range) => value.clamp(*range.start(), *range.end()),
+                None => value,
+            };
+            out.push(kind.convert(value));
+        }
+        Ok(out)
+    }
+}
+
+#[derive(Default)]
+pub struct ArgErrors {
+    pub count: u64,
+    pub last_error: String,
+}
+
+/// Schemas of handled addresses, checked before a message is dispatched
+#[derive(Resource, Default)]
+pub struct OscSchemas {
+    pub schemas: HashMap<String, ArgSchema>,
+    /// Rejected messages per address
+    pub errors: HashMap<String, ArgErrors>,
+}
+
+impl OscSchemas {
+    /// Converts the arguments in place. Rejected messages are counted and shouldn't be dispatched.
+    pub fn check(&mut self, addr: &str, args: &mut Vec<OscType>) -> bool {
+        let Some(schema) = self.schemas.get(addr) else { return true; };
+        match schema.apply(args) {
+            Ok(converted) => {
+                *args = converted;
+                true
+            }
+            Err(e) => {
+                let errors = self.errors.entry(addr.to_owned()).or_default();
+                if errors.last_error != e {
+                    warn!("Rejected OSC message {}: {}", addr, e);
+                    errors.last_error = e;
+                }
+                errors.count += 1;
+                false
+            }
+        }
+    }
+}
+
+pub trait AddOscSchema {
+    fn add_osc_schema(&mut self, addr: &str, schema: ArgSchema) -> &mut Self;
+}
+
+impl AddOscSchema for App {
+    fn add_osc_schema(&mut self, addr: &str, schema: ArgSchema) -> &mut Self {
+        self.init_resource::<OscSchemas>();
+        self.world.resource_mut::<OscSchemas>().schemas.insert(addr.to_owned(), schema);
+        self
+    }
+}
@@ -0,0 +1,160 @@
+use std::io;
+use std::net::{SocketAddr, UdpSocket};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use bevy::prelude::*;
+use bevy_rosc::{MultiAddressOscMethod, OscMethod, SingleAddressOscMethod};
+use rosc::address::Matcher;
+use rosc::{OscMessage, OscPacket, OscTime};
+use crate::config::AppConfig;
+use crate::osc::monitor::OscMonitor;
+use crate::osc::routing::route;
+use crate::osc::schema::OscSchemas;
+use crate::status::StatusPanel;
+
+/// Seconds between the OSC epoch (1900) and the unix epoch
+const OSC_EPOCH_OFFSET: u64 = 2_208_988_800;
+/// Bundles scheduled further ahead are dropped
+const MAX_SCHEDULE_AHEAD: Duration = Duration::from_secs(60);
+const MAX_SCHEDULED: usize = 4096;
+
+/// Receives OSC over UDP and dispatches it to the `bevy_rosc` method components.
+/// Replaces `BevyRoscPlugin` so bundles with a future timetag can be held back.
+pub struct OscServerPlugin;
+
+impl Plugin for OscServerPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<OscMonitor>()
+            .init_resource::<OscSchemas>()
+            .add_system(osc_receive_system)
+            .add_system(osc_dispatch_system.after(osc_receive_system))
+        ;
+    }
+}
+
+/// Message waiting to be dispatched
+#[derive(Clone)]
+pub struct PendingMessage {
+    pub msg: OscMessage,
+    pub source: SocketAddr,
+    /// `None` if it should be applied right away
+    pub due: Option<SystemTime>,
+}
+
+#[derive(Resource)]
+pub struct OscServer {
+    socket: UdpSocket,
+    pub pending: Vec<PendingMessage>,
+}
+
+impl OscServer {
+    pub fn bind(addr: &str) -> io::Result<Self> {
+        let socket = UdpSocket::bind(addr)?;
+        socket.set_nonblocking(true)?;
+        Ok(Self { socket, pending: vec![] })
+    }
+
+    /// Number of messages held back for a future timetag
+    pub fn scheduled(&self) -> usize {
+        self.pending.iter().filter(|p| p.due.is_some()).count()
+    }
+}
+
+/// Converts a timetag to system time, `None` meaning "immediately"
+fn timetag_to_system_time(time: OscTime) -> Option<SystemTime> {
+    if time.seconds == 0 && time.fractional <= 1 { return None; }
+    let secs = (time.seconds as u64).checked_sub(OSC_EPOCH_OFFSET)?;
+    let nanos = (time.fractional as u64 * 1_000_000_000) >> 32;
+    Some(UNIX_EPOCH + Duration::from_secs(secs) + Duration::from_nanos(nanos))
+}
+
+/// Flattens a packet into messages. Nested bundles can't be due before their parent.
+fn unpack(packet: OscPacket, source: SocketAddr, due: Option<SystemTime>, out: &mut Vec<PendingMessage>) {
+    match packet {
+        OscPacket::Message(msg) => out.push(PendingMessage { msg, source, due }),
+        OscPacket::Bundle(bundle) => {
+            let due = match (due, timetag_to_system_time(bundle.timetag)) {
+                (Some(outer), Some(inner)) => Some(outer.max(inner)),
+                (outer, inner) => outer.or(inner),
+            };
+            for packet in bundle.content {
+                unpack(packet, source, due, out);
+            }
+        }
+    }
+}
+
+fn osc_receive_system(
+    mut server: ResMut<OscServer>,
+    mut status: ResMut<StatusPanel>,
+    mut monitor: ResMut<OscMonitor>,
+    time: Res<Time>,
+) {
+    let mut buf = [0u8; rosc::decoder::MTU];
+    let mut received = vec![];
+    loop {
+        match server.socket.recv_from(&mut buf) {
+            Ok((size, source)) => match rosc::decoder::decode_udp(&buf[..size]) {
+                Ok((_, packet)) => unpack(packet, source, None, &mut received),
+                Err(e) => status.warn("osc_decode", format!("Undecodable OSC packet from {}: {:?}", source, e)),
+            },
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+            Err(e) => {
+                status.error("osc_receive", format!("Receiving OSC failed: {}", e));
+                break;
+            }
+        }
+    }
+
+    let now = SystemTime::now();
+    for pending in received {
+        monitor.record(&pending.msg, pending.source, time.elapsed_seconds_f64());
+        match pending.due {
+            Some(due) if due > now + MAX_SCHEDULE_AHEAD => {
+                status.warn("osc_schedule", format!("Dropped OSC bundle for {} scheduled more than {}s ahead", pending.msg.addr, MAX_SCHEDULE_AHEAD.as_secs()));
+            }
+            _ if server.pending.len() >= MAX_SCHEDULED => {
+                status.warn("osc_schedule", "Too many scheduled OSC messages, dropping new ones");
+            }
+            _ => server.pending.push(pending),
+        }
+    }
+}
+
+/// Dispatches due messages. A scheduled message is applied on the frame closest to its
+/// timetag, i.e. once it's due before the middle of the next frame.
+fn osc_dispatch_system(
+    mut server: ResMut<OscServer>,
+    time: Res<Time>,
+    config: Res<AppConfig>,
+    mut status: ResMut<StatusPanel>,
+    mut schemas: ResMut<OscSchemas>,
+    mut single_methods: Query<&mut SingleAddressOscMethod>,
+    mut multi_methods: Query<&mut MultiAddressOscMethod>,
+) {
+    if server.pending.is_empty() { return; }
+    let horizon = SystemTime::now() + time.delta() / 2;
+
+    let (mut due, pending): (Vec<_>, Vec<_>) = std::mem::take(&mut server.pending).into_iter()
+        .partition(|p| p.due.map_or(true, |t| t <= horizon));
+    server.pending = pending;
+    due.sort_by_key(|p| p.due);
+
+    let messages = due.into_iter()
+        .flat_map(|p| route(&config.osc.routes, p.msg, &mut status))
+        .collect::<Vec<_>>();
+    for mut msg in messages {
+        if !schemas.check(&msg.addr, &mut msg.args) { continue; }
+        let Ok(matcher) = Matcher::new(&msg.addr) else { continue; };
+        for mut method in single_methods.iter_mut() {
+            if method.get_addresses().iter().any(|a| matcher.match_address(a)) {
+                method.receive_message(msg.clone());
+            }
+        }
+        for mut method in multi_methods.iter_mut() {
+            if method.get_addresses().iter().any(|a| matcher.match_address(a)) {
+                method.receive_message(msg.clone());
+            }
+        }
+    }
+}
@@ -0,0 +1,345 @@
+use std::fs;
+use std::path::PathBuf;
+use bevy::ecs::event::ManualEventReader;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use bevy_egui::{egui, EguiContexts};
+use rosc::OscType;
+use serde::{Deserialize, Serialize};
+use crate::autosave::{atomic_write, AddModuleState, ModuleState};
+use crate::keybindings::{AddKeyActions, KeyAction};
+use crate::osc::{arg_int, AddOscControls, OscAction};
+use crate::osc::schema::{ArgSchema, ArgSpec};
+
+pub const PRESET_SLOTS: usize = 10;
+const PRESET_DIR: &str = "presets";
+
+const RECALL_KEYS: [&str; PRESET_SLOTS] = [
+    "ctrl+1", "ctrl+2", "ctrl+3", "ctrl+4", "ctrl+5", "ctrl+6", "ctrl+7", "ctrl+8", "ctrl+9", "ctrl+0",
+];
+const STORE_KEYS: [&str; PRESET_SLOTS] = [
+    "ctrl+shift+1", "ctrl+shift+2", "ctrl+shift+3", "ctrl+shift+4", "ctrl+shift+5",
+    "ctrl+shift+6", "ctrl+shift+7", "ctrl+shift+8", "ctrl+shift+9", "ctrl+shift+0",
+];
+
+pub struct PresetPlugin;
+
+impl Plugin for PresetPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_event::<PresetEvent>()
+            .init_resource::<PresetSlotNames>()
+            .init_resource::<PresetControls>()
+
+            .add_osc_actions(preset_osc_actions())
+            .add_key_actions(preset_key_actions())
+            .add_system(preset_ui)
+        ;
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PresetModule {
+    Feedback,
+    RD,
+    ChipSpin,
+    Wipes,
+}
+
+impl PresetModule {
+    pub const ALL: [PresetModule; 4] = [PresetModule::Feedback, PresetModule::RD, PresetModule::ChipSpin, PresetModule::Wipes];
+
+    /// Name used in OSC addresses and file names, e.g. `/vj/preset/rd/recall`
+    pub fn osc_name(&self) -> &'static str {
+        match self {
+            PresetModule::Feedback => "feedback",
+            PresetModule::RD => "rd",
+            PresetModule::ChipSpin => "chip",
+            PresetModule::Wipes => "wipes",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PresetModule::Feedback => "Feedback",
+            PresetModule::RD => "RD",
+            PresetModule::ChipSpin => "Chip Spin",
+            PresetModule::Wipes => "Wipes",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PresetAction {
+    Store,
+    Recall,
+}
+
+#[derive(Clone)]
+pub struct PresetEvent {
+    pub module: PresetModule,
+    pub action: PresetAction,
+    pub slot: usize,
+    /// Name given to the preset when storing
+    pub name: String,
+}
+
+impl PresetEvent {
+    pub fn recall(module: PresetModule, slot: usize) -> Self {
+        Self { module, action: PresetAction::Recall, slot, name: String::new() }
+    }
+
+    pub fn store(module: PresetModule, slot: usize, name: String) -> Self {
+        let name = match name.trim() {
+            "" => format!("Slot {}", slot + 1),
+            name => name.to_owned(),
+        };
+        Self { module, action: PresetAction::Store, slot, name }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Preset<T> {
+    pub name: String,
+    pub data: T,
+}
+
+/// Preset slots of one module, persisted to `presets/<module>.ron`
+#[derive(Resource)]
+pub struct PresetBank<T: ModuleState> {
+    pub module: PresetModule,
+    pub slots: Vec<Option<Preset<T>>>,
+}
+
+impl<T: ModuleState> PresetBank<T> {
+    fn path(module: PresetModule) -> PathBuf {
+        PathBuf::from(PRESET_DIR).join(format!("{}.ron", module.osc_name()))
+    }
+
+    pub fn factory(module: PresetModule, factory: Vec<(&str, T)>) -> Self {
+        let mut slots: Vec<_> = factory.into_iter().map(|(name, data)| Some(Preset { name: name.to_owned(), data })).collect();
+        slots.resize(PRESET_SLOTS, None);
+        Self { module, slots }
+    }
+
+    /// Replaces the slots with the saved ones, keeping the factory presets if there are none
+    pub fn load(&mut self) {
+        let path = Self::path(self.module);
+        let Ok(s) = fs::read_to_string(&path) else { return; };
+        match ron::from_str::<Vec<Option<Preset<T>>>>(&s) {
+            Ok(mut slots) => {
+                slots.resize(PRESET_SLOTS, None);
+                self.slots = slots;
+            }
+            Err(e) => error!("Failed to parse {:?}, using factory presets: {}", path, e),
+        }
+    }
+
+    pub fn save(&self) {
+        let path = Self::path(self.module);
+        let result = fs::create_dir_all(PRESET_DIR)
+            .map_err(|e| e.to_string())
+            .and_then(|_| ron::ser::to_string_pretty(&self.slots, ron::ser::PrettyConfig::default()).map_err(|e| e.to_string()))
+            .and_then(|s| atomic_write(&path, &s).map_err(|e| e.to_string()));
+        if let Err(e) = result {
+            error!("Failed to save presets to {:?}: {}", path, e);
+        }
+    }
+
+    pub fn names(&self) -> Vec<Option<String>> {
+        self.slots.iter().map(|s| s.as_ref().map(|p| p.name.clone())).collect()
+    }
+}
+
+/// Slot names of every registered bank, for UIs that don't know the preset types
+#[derive(Resource, Default)]
+pub struct PresetSlotNames(pub HashMap<PresetModule, Vec<Option<String>>>);
+
+pub trait AddPresetBank {
+    /// Registers a preset bank for a module. The state is also included in autosaves.
+    fn add_preset_bank<T: ModuleState>(&mut self, module: PresetModule, factory: Vec<(&str, T)>) -> &mut Self;
+}
+
+impl AddPresetBank for App {
+    fn add_preset_bank<T: ModuleState>(&mut self, module: PresetModule, factory: Vec<(&str, T)>) -> &mut Self {
+        let bank = PresetBank::<T>::factory(module, factory);
+        self.init_resource::<PresetSlotNames>();
+        self.world.resource_mut::<PresetSlotNames>().0.insert(module, bank.names());
+
+        self
+            .add_event::<PresetEvent>()
+            .insert_resource(bank)
+            .add_module_state::<T>()
+            .add_startup_system(preset_bank_load_system::<T>)
+            .add_system(preset_bank_system::<T>)
+    }
+}
+
+fn preset_bank_load_system<T: ModuleState>(
+    mut bank: ResMut<PresetBank<T>>,
+    mut names: ResMut<PresetSlotNames>,
+) {
+    bank.load();
+    names.0.insert(bank.module, bank.names());
+}
+
+fn preset_bank_system<T: ModuleState>(
+    world: &mut World,
+    mut reader: Local<ManualEventReader<PresetEvent>>,
+) {
+    let module = world.resource::<PresetBank<T>>().module;
+    let events: Vec<PresetEvent> = reader.iter(world.resource::<Events<PresetEvent>>())
+        .filter(|e| e.module == module)
+        .cloned()
+        .collect();
+
+    for event in events {
+        if event.slot >= PRESET_SLOTS {
+            warn!("Preset slot {} out of range", event.slot);
+            continue;
+        }
+        match event.action {
+            PresetAction::Store => {
+                let Some(data) = T::capture(world) else {
+                    warn!("Can't store {} preset, module state not available", module.label());
+                    continue;
+                };
+                let mut bank = world.resource_mut::<PresetBank<T>>();
+                bank.slots[event.slot] = Some(Preset { name: event.name, data });
+                bank.save();
+                let names = bank.names();
+                world.resource_mut::<PresetSlotNames>().0.insert(module, names);
+            }
+            PresetAction::Recall => {
+                let preset = world.resource::<PresetBank<T>>().slots[event.slot].clone();
+                if let Some(preset) = preset {
+                    preset.data.apply(world);
+                }
+            }
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct PresetControls {
+    /// Module targeted by the number key hotkeys
+    pub module: PresetModule,
+    /// Name given to the next stored preset
+    pub name: String,
+}
+
+impl Default for PresetControls {
+    fn default() -> Self {
+        Self {
+            module: PresetModule::Feedback,
+            name: String::new(),
+        }
+    }
+}
+
+/// Ctrl + number recalls a slot of the selected module, Ctrl + Shift + number stores it
+fn preset_key_actions() -> Vec<KeyAction> {
+    (0..PRESET_SLOTS)
+        .flat_map(|slot| [
+            KeyAction::trigger(
+                format!("preset_recall_{}", slot + 1), format!("Recall preset {}", slot + 1), Some(RECALL_KEYS[slot]),
+                move |world| {
+                    let module = world.resource::<PresetControls>().module;
+                    world.resource_mut::<Events<PresetEvent>>().send(PresetEvent::recall(module, slot));
+                },
+            ),
+            KeyAction::trigger(
+                format!("preset_store_{}", slot + 1), format!("Store preset {}", slot + 1), Some(STORE_KEYS[slot]),
+                move |world| {
+                    let mut controls = world.resource_mut::<PresetControls>();
+                    let event = PresetEvent::store(controls.module, slot, std::mem::take(&mut controls.name));
+                    world.resource_mut::<Events<PresetEvent>>().send(event);
+                },
+            ),
+        ])
+        .collect()
+}
+
+/// `/vj/preset/<module>/<store|recall> <slot>`
+fn preset_osc_actions() -> Vec<OscAction> {
+    PresetModule::ALL.into_iter()
+        .flat_map(|module| [
+            OscAction::new(
+                format!("preset/{}/store", module.osc_name()), format!("Store {} preset", module.label()), "i",
+                move |world, args| {
+                    let slot = preset_slot_arg(args)?;
+                    world.resource_mut::<Events<PresetEvent>>().send(PresetEvent::store(module, slot, String::new()));
+                    Ok(())
+                },
+            ).with_schema(slot_schema()),
+            OscAction::new(
+                format!("preset/{}/recall", module.osc_name()), format!("Recall {} preset", module.label()), "i",
+                move |world, args| {
+                    let slot = preset_slot_arg(args)?;
+                    world.resource_mut::<Events<PresetEvent>>().send(PresetEvent::recall(module, slot));
+                    Ok(())
+                },
+            ).with_schema(slot_schema()),
+        ])
+        .collect()
+}
+
+fn slot_schema() -> ArgSchema {
+    ArgSchema::new(vec![ArgSpec::int("slot", 0..=PRESET_SLOTS as i32 - 1)])
+}
+
+fn preset_slot_arg(args: &[OscType]) -> Result<usize, String> {
+    match args.first().and_then(arg_int) {
+        Some(slot) if slot >= 0 && (slot as usize) < PRESET_SLOTS => Ok(slot as usize),
+        Some(slot) => Err(format!("preset slot {} out of range", slot)),
+        None => Err("expected a slot number".to_owned()),
+    }
+}
+
+pub fn preset_ui(
+    mut contexts: EguiContexts,
+    names: Res<PresetSlotNames>,
+    mut controls: ResMut<PresetControls>,
+    mut event_writer: EventWriter<PresetEvent>,
+) {
+    egui::Window::new("Presets").show(contexts.ctx_mut(), |ui| {
+        ui.horizontal(|ui| {
+            for module in PresetModule::ALL {
+                if names.0.contains_key(&module) {
+                    ui.selectable_value(&mut controls.module, module, module.label());
+                }
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("Name");
+            ui.text_edit_singleline(&mut controls.name);
+        });
+
+        ui.separator();
+
+        let Some(slot_names) = names.0.get(&controls.module) else {
+            ui.label("Module not available");
+            return;
+        };
+
+        egui::Grid::new("preset slots")
+            .num_columns(3)
+            .spacing([40.0, 4.0])
+            .striped(true)
+            .show(ui, |ui| {
+                for (slot, name) in slot_names.iter().enumerate() {
+                    ui.label(format!("{}", (slot + 1) % 10));
+                    if ui.add_enabled(name.is_some(), egui::Button::new(name.as_deref().unwrap_or("-"))).clicked() {
+                        event_writer.send(PresetEvent::recall(controls.module, slot));
+                    }
+                    if ui.button("Store").clicked() {
+                        let name = std::mem::take(&mut controls.name);
+                        event_writer.send(PresetEvent::store(controls.module, slot, name));
+                    }
+                    ui.end_row();
+                }
+            });
+
+        ui.label("Ctrl+Num: recall, Ctrl+Shift+Num: store");
+    });
+}
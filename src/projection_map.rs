@@ -1,5 +1,3 @@
-use crate::fractal::FractalRenderTarget;
-use crate::rd::RDRenderTarget;
 use bevy::core_pipeline::clear_color::ClearColorConfig;
 use bevy::prelude::*;
 use bevy::render::camera::{RenderTarget, ScalingMode};
@@ -10,8 +8,9 @@ pub struct ProjectionMapPlugin;
 
 impl Plugin for ProjectionMapPlugin {
     fn build(&self, app: &mut App) {
-        app.add_startup_system(setup).add_system(ui_system)
-            .add_system(chip_card_system);
+        app.add_startup_system(setup).add_system(ui_system);
+        #[cfg(feature = "chipspin")]
+        app.add_system(chip_card_system);
     }
 }
 
@@ -22,10 +21,7 @@ fn setup(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut color_materials: ResMut<Assets<ColorMaterial>>,
-    fractal_rt: Res<FractalRenderTarget>,
-    rd_rt: Res<RDRenderTarget>,
-    feedback_rt: Res<FeedbackShaderRenderTarget>,
-    chipsin: Res<ChipSpinTexture>,
+    shared_textures: Res<SharedTextures>,
 ) {
     commands.spawn(Camera2dBundle {
         camera_2d: Camera2d {
@@ -44,7 +40,7 @@ fn setup(
 
     // Top rectangle
     commands.spawn(SpriteBundle {
-        texture: feedback_rt.render_target.clone(),
+        texture: shared_textures.feedback.clone(),
         sprite: Sprite {
             custom_size: Some(Vec2::new(1920., 610.)),
             ..default()
@@ -76,7 +72,7 @@ fn setup(
     let mesh_handle = Mesh2dHandle(meshes.add(parallelogram));
 
     let mat_handle = color_materials.add(ColorMaterial {
-        texture: Some(feedback_rt.render_target.clone()),
+        texture: Some(shared_textures.feedback.clone()),
         ..default()
     });
     commands.spawn(ColorMesh2dBundle {
@@ -110,13 +106,23 @@ fn setup(
     });*/
 
     // CHIPS
-    let mesh = meshes.add(shape::Quad::new(Vec2::splat(100.)).into());
+    #[cfg(feature = "chipspin")]
+    spawn_chip_cards(&mut commands, &mut meshes, &mut color_materials, &shared_textures);
+}
+
+#[cfg(feature = "chipspin")]
+fn spawn_chip_cards(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    color_materials: &mut Assets<ColorMaterial>,
+    shared_textures: &SharedTextures,
+) {
     commands.spawn((MaterialMesh2dBundle {
         mesh: meshes
             .add(shape::Quad::new(Vec2::splat(600.)).into())
             .into(),
         material: color_materials.add(ColorMaterial {
-            texture: Some(chipsin.texture.clone()),
+            texture: Some(shared_textures.chip_spin.clone()),
             ..default()
         }),
         transform: Transform::from_translation(Vec3::new(400., 250., 10.)),
@@ -128,7 +134,7 @@ fn setup(
             .add(shape::Quad::new(Vec2::splat(600.)).into())
             .into(),
         material: color_materials.add(ColorMaterial {
-            texture: Some(chipsin.texture.clone()),
+            texture: Some(shared_textures.chip_spin.clone()),
             ..default()
         }),
         transform: Transform::from_translation(Vec3::new(-400., 250., 10.)),
@@ -145,18 +151,21 @@ use bevy_egui::egui::Shape;
 use bevy_pyree::beat::BeatEvent;
 use rand::random;
 use crate::beat_controls::BeatMute;
-use crate::chipspin::{ChipSpinStateResource, ChipSpinTexture};
-use crate::feedback_shader::{FeedbackShaderMaterial, FeedbackShaderRenderTarget};
+#[cfg(feature = "chipspin")]
+use crate::chipspin::ChipSpinStateResource;
+use crate::config::AppConfig;
+use crate::shared_textures::SharedTextures;
+#[cfg(feature = "traktor")]
 use crate::traktor_beat::TraktorBeat;
 
 
-pub fn ui_system(mut contexts: EguiContexts, mut commands: Commands) {
+pub fn ui_system(mut contexts: EguiContexts, mut commands: Commands, config: Res<AppConfig>) {
     egui::Window::new("Projection Map").show(contexts.ctx_mut(), |ui| {
         if ui.button("Spawn Window").clicked() {
             let second_window = commands
                 .spawn(Window {
                     title: "VJ Pyree output".to_owned(),
-                    resolution: WindowResolution::new(1920.0, 1080.0)
+                    resolution: WindowResolution::new(config.output.width, config.output.height)
                         .with_scale_factor_override(1.0),
                     present_mode: PresentMode::AutoVsync,
                     ..Default::default()
@@ -190,15 +199,23 @@ fn pt1_param(u: &mut f32, y: f32, pt1: f32, dt: f32)
     *u = *u + (y - *u) * (dt/(pt1+dt))
 }
 
+#[cfg(feature = "chipspin")]
 pub fn chip_card_system(
-    chip_spin_state: Res<ChipSpinStateResource>,
+    chip_spin_state: Option<Res<ChipSpinStateResource>>,
     mut query: Query<&mut Visibility, With<ChipSpinCard>>,
     mut transform_query: Query<&mut Transform, With<ChipSpinCard>>,
-    traktor: Res<TraktorBeat>,
+    #[cfg(feature = "traktor")]
+    traktor: Option<Res<TraktorBeat>>,
     mut event_listener: EventReader<BeatEvent>,
     mut beat_mute: Res<BeatMute>,
     time: Res<Time>,
 ) {
+    // Chip spin disabled in the config, the cards would only show the placeholder
+    let Some(chip_spin_state) = chip_spin_state else {
+        for mut vis in query.iter_mut() { *vis = Visibility::Hidden; }
+        return;
+    };
+
     for mut vis in query.iter_mut() {
         *vis = match chip_spin_state.visible {
             true => Visibility::Visible,
@@ -219,8 +236,13 @@ pub fn chip_card_system(
         if beat_mute.mute { continue; }
         if chip_spin_state.jump == false { continue; }
 
+        #[cfg(feature = "traktor")]
+        let volume = traktor.as_ref().map(|t| t.last_volume as f32 / 128.).unwrap_or(1.);
+        #[cfg(not(feature = "traktor"))]
+        let volume = 1.;
+
         for mut transform in transform_query.iter_mut() {
-            let strength = (traktor.last_volume as f32 / 128.) * chip_spin_state.pt1_strength;
+            let strength = volume * chip_spin_state.pt1_strength;
             let mirror = match (transform.translation.x > 0.) {
                 true => 1.,
                 false => -1.,
@@ -0,0 +1,136 @@
+use bevy::prelude::*;
+use rand::random;
+use crate::keybindings::KeyAction;
+use crate::osc::{arg_f32, MaterialCopy, OscAction};
+use crate::osc::schema::{ArgSchema, ArgSpec};
+use crate::rd::RDShaderMaterial;
+
+/// One-off operations on the whole field, run by the first simulation pass of a frame
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(usize)]
+pub enum RDAction {
+    /// Back to the base state, all A and no B
+    Clear,
+    /// Base state with blobs of B
+    Noise,
+    /// Swaps A and B
+    Invert,
+}
+
+impl RDAction {
+    pub const ALL: [RDAction; 3] = [RDAction::Clear, RDAction::Noise, RDAction::Invert];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            RDAction::Clear => "Clear",
+            RDAction::Noise => "Noise",
+            RDAction::Invert => "Invert",
+        }
+    }
+
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+
+    /// Value of `uniform_action` in `rd.wgsl`, 0 is no action
+    fn code(&self) -> u32 {
+        match self {
+            RDAction::Clear => 1,
+            RDAction::Noise => 2,
+            RDAction::Invert => 3,
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct RDActions {
+    /// Runs with the next simulation steps
+    pub pending: Option<RDAction>,
+    /// Share of the field covered by noise blobs
+    pub noise_density: f32,
+}
+
+impl Default for RDActions {
+    fn default() -> Self {
+        Self { pending: None, noise_density: 0.1 }
+    }
+}
+
+/// Runs an `RDAction` every `beat_div` beats
+#[derive(Resource)]
+pub struct RDActionAutomation {
+    pub action: Option<RDAction>,
+    pub beat_div: usize,
+    pub beat_count: usize,
+}
+
+impl Default for RDActionAutomation {
+    fn default() -> Self {
+        Self { action: None, beat_div: 16, beat_count: 0 }
+    }
+}
+
+/// Hands pending actions to the shader, the step system makes sure they run this frame
+pub fn rd_action_system(
+    mut events: EventReader<RDAction>,
+    mut actions: ResMut<RDActions>,
+    mat_query: Query<&Handle<RDShaderMaterial>, Without<MaterialCopy>>,
+    mut materials: ResMut<Assets<RDShaderMaterial>>,
+) {
+    if let Some(action) = events.iter().last() {
+        actions.pending = Some(*action);
+    }
+
+    // Every mutable access rebuilds the bind group, so the material is only touched to start or clear an action
+    let Ok(handle) = mat_query.get_single() else { return; };
+    let running = materials.get(handle).map_or(false, |m| m.action != 0);
+    match actions.pending {
+        Some(action) => {
+            let Some(mat) = materials.get_mut(handle) else { return; };
+            mat.action = action.code();
+            mat.action_params = Vec4::new(random::<f32>() * 1000., actions.noise_density, 0., 0.);
+        }
+        // Ran last frame
+        None if running => {
+            if let Some(mat) = materials.get_mut(handle) {
+                mat.action = 0;
+            }
+        }
+        None => {}
+    }
+}
+
+/// `/vj/rd/clear`, `/vj/rd/noise [density]` and `/vj/rd/invert`
+pub fn action_osc_actions() -> Vec<OscAction> {
+    RDAction::ALL.into_iter()
+        .map(|action| {
+            let osc_action = OscAction::new(
+                format!("rd/{}", action.name().to_lowercase()), format!("{} the reaction-diffusion field", action.name()),
+                if action == RDAction::Noise { "f" } else { "" },
+                move |world, args| {
+                    if let Some(density) = args.get(0).and_then(arg_f32) {
+                        world.resource_mut::<RDActions>().noise_density = density.clamp(0., 1.);
+                    }
+                    world.resource_mut::<Events<RDAction>>().send(action);
+                    Ok(())
+                },
+            );
+            match action {
+                RDAction::Noise => osc_action.with_schema(ArgSchema::new(vec![
+                    ArgSpec::float("density", 0. ..=1.).optional(),
+                ])),
+                _ => osc_action,
+            }
+        })
+        .collect()
+}
+
+pub fn action_key_actions() -> Vec<KeyAction> {
+    const KEYS: [&str; 3] = ["8", "9", "0"];
+    RDAction::ALL.into_iter().zip(KEYS)
+        .map(|(action, key)| KeyAction::trigger(
+            format!("rd_{}", action.name().to_lowercase()), format!("{} the reaction-diffusion field", action.name()), Some(key),
+            move |world| world.resource_mut::<Events<RDAction>>().send(action),
+        ))
+        .collect()
+}
@@ -0,0 +1,246 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use crate::osc::MaterialCopy;
+use crate::rd::kernel::{Kernel, RDKernel};
+use crate::rd::models::RDModel;
+use crate::rd::RDShaderMaterial;
+
+/// Time step of `rd1` in `rd.wgsl`
+const DT: f32 = 0.7;
+
+const PREVIEW_SIZE: usize = 64;
+const PREVIEW_STEPS_PER_FRAME: usize = 20;
+const PREVIEW_STEPS: usize = 4000;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GrayScottParams {
+    pub da: f32,
+    pub db: f32,
+    pub feed: f32,
+    pub kill: f32,
+}
+
+impl GrayScottParams {
+    pub fn from_material(mat: &RDShaderMaterial) -> Self {
+        Self { da: mat.da, db: mat.db, feed: mat.feed, kill: mat.kill }
+    }
+}
+
+/// Where a simulation ends up
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// B is gone, the field is back to the base state
+    Dies,
+    Stable,
+    /// Values ran off to infinity
+    Explodes,
+}
+
+impl Outcome {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Outcome::Dies => "Dies",
+            Outcome::Stable => "Stable",
+            Outcome::Explodes => "Explodes",
+        }
+    }
+}
+
+/// Gray-Scott step of `rd.wgsl` on the CPU, without the parameter map.
+/// Wraps around at the edges like the render target sampler. Runs in f32, the GPU keeps the state in f16.
+#[derive(Clone)]
+pub struct CpuGrayScott {
+    pub width: usize,
+    pub height: usize,
+    /// A and B per cell, row by row
+    pub cells: Vec<[f32; 2]>,
+    back: Vec<[f32; 2]>,
+    pub steps: usize,
+    /// `RDKernel::weights` of the shader being mirrored
+    pub kernel: Kernel,
+}
+
+impl CpuGrayScott {
+    /// Base state, all A and no B
+    pub fn new(width: usize, height: usize, kernel: Kernel) -> Self {
+        Self { width, height, cells: vec![[1., 0.]; width * height], back: vec![[1., 0.]; width * height], steps: 0, kernel }
+    }
+
+    /// A square of B in the middle like the green seed shapes, `size` is relative to the smaller side
+    pub fn seed_center(&mut self, size: f32) {
+        let half = (self.width.min(self.height) as f32 * size / 2.) as isize;
+        let (cx, cy) = (self.width as isize / 2, self.height as isize / 2);
+        for y in cy - half..cy + half {
+            for x in cx - half..cx + half {
+                let i = self.index(x, y);
+                self.cells[i] = [0., 1.];
+            }
+        }
+    }
+
+    fn index(&self, x: isize, y: isize) -> usize {
+        let x = x.rem_euclid(self.width as isize) as usize;
+        let y = y.rem_euclid(self.height as isize) as usize;
+        y * self.width + x
+    }
+
+    fn laplace(&self, x: isize, y: isize) -> [f32; 2] {
+        let mut sum = [0., 0.];
+        for (ky, row) in self.kernel.iter().enumerate() {
+            for (kx, weight) in row.iter().enumerate() {
+                let cell = self.cells[self.index(x + kx as isize - 2, y + ky as isize - 2)];
+                sum[0] += cell[0] * weight;
+                sum[1] += cell[1] * weight;
+            }
+        }
+        sum
+    }
+
+    pub fn step(&mut self, params: GrayScottParams) {
+        for y in 0..self.height as isize {
+            for x in 0..self.width as isize {
+                let i = self.index(x, y);
+                let [a, b] = self.cells[i];
+                let lap = self.laplace(x, y);
+                let reaction = a * b * b;
+                self.back[i] = [
+                    a + (params.da * lap[0] - reaction + params.feed * (1. - a).clamp(0., 1.)) * DT,
+                    b + (params.db * lap[1] + reaction - params.kill.clamp(0., 1.) * b) * DT,
+                ];
+            }
+        }
+        std::mem::swap(&mut self.cells, &mut self.back);
+        self.steps += 1;
+    }
+
+    pub fn outcome(&self) -> Outcome {
+        if self.cells.iter().flatten().any(|v| !v.is_finite() || v.abs() > 10.) {
+            Outcome::Explodes
+        } else if self.cells.iter().all(|c| c[1] < 1e-3) {
+            Outcome::Dies
+        } else {
+            Outcome::Stable
+        }
+    }
+
+    /// B as a grayscale image
+    pub fn image(&self) -> egui::ColorImage {
+        let pixels = self.cells.iter()
+            .map(|c| {
+                let v = (c[1].clamp(0., 1.) * 255.) as u8;
+                egui::Color32::from_gray(v)
+            })
+            .collect();
+        egui::ColorImage { size: [self.width, self.height], pixels }
+    }
+}
+
+/// Runs the current Gray-Scott parameters on the CPU, a few steps per frame
+#[derive(Resource)]
+pub struct CpuPreview {
+    sim: CpuGrayScott,
+    params: Option<(GrayScottParams, Kernel)>,
+    texture: Option<egui::TextureHandle>,
+}
+
+impl Default for CpuPreview {
+    fn default() -> Self {
+        Self { sim: CpuGrayScott::new(PREVIEW_SIZE, PREVIEW_SIZE, RDKernel::default().weights()), params: None, texture: None }
+    }
+}
+
+pub fn cpu_preview_ui(
+    mut contexts: EguiContexts,
+    mut preview: ResMut<CpuPreview>,
+    mat_query: Query<&Handle<RDShaderMaterial>, Without<MaterialCopy>>,
+    materials: Res<Assets<RDShaderMaterial>>,
+    kernel: Res<RDKernel>,
+) {
+    let Some(mat) = mat_query.get_single().ok().and_then(|h| materials.get(h)) else { return; };
+    let ctx = contexts.ctx_mut();
+
+    egui::Window::new("RD Preview").default_open(false).show(ctx, |ui| {
+        if RDModel::from_code(mat.model) != RDModel::GrayScott {
+            ui.label("Only available for Gray-Scott");
+            return;
+        }
+
+        let params = GrayScottParams::from_material(mat);
+        let weights = kernel.weights();
+        let restart = ui.button("Restart").clicked();
+        if restart || preview.params != Some((params, weights)) {
+            preview.params = Some((params, weights));
+            preview.sim = CpuGrayScott::new(PREVIEW_SIZE, PREVIEW_SIZE, weights);
+            preview.sim.seed_center(0.2);
+        }
+        if preview.sim.steps < PREVIEW_STEPS && preview.sim.outcome() != Outcome::Explodes {
+            for _ in 0..PREVIEW_STEPS_PER_FRAME {
+                preview.sim.step(params);
+            }
+        }
+
+        let image = preview.sim.image();
+        match preview.texture.as_mut() {
+            Some(texture) => texture.set(image, egui::TextureOptions::NEAREST),
+            None => preview.texture = Some(ui.ctx().load_texture("rd cpu preview", image, egui::TextureOptions::NEAREST)),
+        }
+        if let Some(texture) = &preview.texture {
+            ui.image(texture.id(), egui::vec2(256., 256.));
+        }
+        ui.label(format!("{} steps, {}", preview.sim.steps, preview.sim.outcome().name()));
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RINGS: GrayScottParams = GrayScottParams { da: 1., db: 0.3, feed: 0.0287, kill: 0.078 };
+
+    fn run(params: GrayScottParams, steps: usize) -> Outcome {
+        let mut sim = CpuGrayScott::new(32, 32, RDKernel::default().weights());
+        sim.seed_center(0.3);
+        for _ in 0..steps {
+            sim.step(params);
+        }
+        sim.outcome()
+    }
+
+    #[test]
+    fn rings_are_stable() {
+        assert_eq!(run(RINGS, 1500), Outcome::Stable);
+    }
+
+    #[test]
+    fn high_kill_dies() {
+        assert_eq!(run(GrayScottParams { kill: 0.2, ..RINGS }, 1500), Outcome::Dies);
+    }
+
+    #[test]
+    fn oversized_diffusion_explodes() {
+        assert_eq!(run(GrayScottParams { da: 4., db: 4., ..RINGS }, 100), Outcome::Explodes);
+    }
+
+    #[test]
+    fn single_step() {
+        // On 5x5 every tap of the smooth kernel hits a different cell
+        let mut sim = CpuGrayScott::new(5, 5, RDKernel::default().weights());
+        sim.cells[2 * 5 + 2] = [0., 1.];
+        sim.step(RINGS);
+
+        let expected = [
+            // Centre: A flows in from all sides (weights sum to 2), B flows out
+            (2, 2, [(1. * 2. + 0.0287) * 0.7, 1. + (0.3 * -2. - 0.078) * 0.7]),
+            // Direct neighbour, weight 0.25 * 0.8 * 1
+            (3, 2, [1. - 0.2 * 0.7, 0.3 * 0.2 * 0.7]),
+            // Corner, weight 0.25 * 0.2 * 0.2
+            (0, 0, [1. - 0.01 * 0.7, 0.3 * 0.01 * 0.7]),
+        ];
+        for (x, y, [a, b]) in expected {
+            let [sim_a, sim_b] = sim.cells[y * 5 + x];
+            assert!((sim_a - a).abs() < 1e-5, "A at ({x}, {y}) is {sim_a}, expected {a}");
+            assert!((sim_b - b).abs() < 1e-5, "B at ({x}, {y}) is {sim_b}, expected {b}");
+        }
+        assert_eq!(sim.steps, 1);
+    }
+}
@@ -0,0 +1,263 @@
+use std::ops::RangeInclusive;
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use crate::osc::MaterialCopy;
+use crate::rd::kernel::{Kernel, RDKernel};
+use crate::rd::cpu::{CpuGrayScott, GrayScottParams, Outcome};
+use crate::rd::models::RDModel;
+use crate::rd::RDShaderMaterial;
+
+/// Thumbnails per side of the map
+const GRID: usize = 8;
+const THUMB_SIZE: usize = 32;
+const THUMB_STEPS: usize = 1500;
+const THUMB_STEPS_PER_FRAME: usize = 100;
+const MAP_SIZE: f32 = 384.;
+
+/// Known regimes from the literature, as (name, feed, kill). They were found with Db/Da = 0.5, so they are only a hint.
+const REGIMES: [(&str, f32, f32); 6] = [
+    ("Spots", 0.030, 0.062),
+    ("Stripes", 0.029, 0.057),
+    ("Mitosis", 0.0367, 0.0649),
+    ("Coral", 0.0545, 0.062),
+    ("Worms", 0.078, 0.061),
+    ("Waves", 0.014, 0.045),
+];
+
+/// Moves feed and kill to the target over `duration`
+struct Glide {
+    from: Vec2,
+    to: Vec2,
+    elapsed: f32,
+    duration: f32,
+}
+
+/// Feed/kill plane with CPU rendered thumbnails of the current diffusion rates
+#[derive(Resource)]
+pub struct RDExplorer {
+    pub feed: RangeInclusive<f32>,
+    pub kill: RangeInclusive<f32>,
+    pub glide: bool,
+    pub glide_time: f32,
+    active_glide: Option<Glide>,
+    /// Rates, bounds and kernel the thumbnails were rendered with
+    rendered: Option<([f32; 6], Kernel)>,
+    atlas: egui::ColorImage,
+    texture: Option<egui::TextureHandle>,
+    /// Thumbnail being simulated
+    job: Option<(usize, CpuGrayScott)>,
+    outcomes: Vec<Option<Outcome>>,
+}
+
+impl Default for RDExplorer {
+    fn default() -> Self {
+        Self {
+            feed: 0.0..=0.1,
+            kill: 0.04..=0.1,
+            glide: true,
+            glide_time: 2.,
+            active_glide: None,
+            rendered: None,
+            atlas: egui::ColorImage::new([GRID * THUMB_SIZE; 2], egui::Color32::BLACK),
+            texture: None,
+            job: None,
+            outcomes: vec![None; GRID * GRID],
+        }
+    }
+}
+
+impl RDExplorer {
+    /// Feed and kill in the middle of a thumbnail, row 0 is the highest feed
+    fn cell_params(&self, cell: usize, da: f32, db: f32) -> GrayScottParams {
+        let (x, y) = (cell % GRID, cell / GRID);
+        let kill = lerp(&self.kill, (x as f32 + 0.5) / GRID as f32);
+        let feed = lerp(&self.feed, 1. - (y as f32 + 0.5) / GRID as f32);
+        GrayScottParams { da, db, feed, kill }
+    }
+
+    /// Advances the thumbnail simulation, restarting everything when the rates or bounds changed
+    fn render_thumbnails(&mut self, da: f32, db: f32, kernel: Kernel) {
+        let key = ([da, db, *self.feed.start(), *self.feed.end(), *self.kill.start(), *self.kill.end()], kernel);
+        if self.rendered != Some(key) {
+            self.rendered = Some(key);
+            self.atlas = egui::ColorImage::new([GRID * THUMB_SIZE; 2], egui::Color32::BLACK);
+            self.outcomes = vec![None; GRID * GRID];
+            self.job = Some((0, seeded_thumbnail(kernel)));
+        }
+
+        let Some((cell, mut sim)) = self.job.take() else { return; };
+        let params = self.cell_params(cell, da, db);
+        for _ in 0..THUMB_STEPS_PER_FRAME {
+            sim.step(params);
+        }
+        let outcome = sim.outcome();
+        if sim.steps < THUMB_STEPS && outcome != Outcome::Explodes {
+            self.job = Some((cell, sim));
+            return;
+        }
+
+        let (x, y) = (cell % GRID * THUMB_SIZE, cell / GRID * THUMB_SIZE);
+        let thumbnail = sim.image();
+        for row in 0..THUMB_SIZE {
+            let dst = (y + row) * GRID * THUMB_SIZE + x;
+            self.atlas.pixels[dst..dst + THUMB_SIZE].copy_from_slice(&thumbnail.pixels[row * THUMB_SIZE..(row + 1) * THUMB_SIZE]);
+        }
+        self.outcomes[cell] = Some(outcome);
+        self.job = (cell + 1 < GRID * GRID).then(|| (cell + 1, seeded_thumbnail(kernel)));
+        if let Some(texture) = &mut self.texture {
+            texture.set(self.atlas.clone(), egui::TextureOptions::LINEAR);
+        }
+    }
+
+    /// Position on the map of a feed/kill pair
+    fn to_screen(&self, rect: egui::Rect, feed: f32, kill: f32) -> egui::Pos2 {
+        egui::pos2(
+            egui::lerp(rect.left()..=rect.right(), inverse_lerp(&self.kill, kill)),
+            egui::lerp(rect.bottom()..=rect.top(), inverse_lerp(&self.feed, feed)),
+        )
+    }
+
+    fn from_screen(&self, rect: egui::Rect, pos: egui::Pos2) -> (f32, f32) {
+        let kill = lerp(&self.kill, (pos.x - rect.left()) / rect.width());
+        let feed = lerp(&self.feed, (rect.bottom() - pos.y) / rect.height());
+        (feed, kill)
+    }
+}
+
+fn seeded_thumbnail(kernel: Kernel) -> CpuGrayScott {
+    let mut sim = CpuGrayScott::new(THUMB_SIZE, THUMB_SIZE, kernel);
+    sim.seed_center(0.3);
+    sim
+}
+
+fn lerp(range: &RangeInclusive<f32>, t: f32) -> f32 {
+    range.start() + (range.end() - range.start()) * t
+}
+
+fn inverse_lerp(range: &RangeInclusive<f32>, v: f32) -> f32 {
+    (v - range.start()) / (range.end() - range.start())
+}
+
+pub fn rd_explorer_ui(
+    mut contexts: EguiContexts,
+    mut explorer: ResMut<RDExplorer>,
+    mat_query: Query<&Handle<RDShaderMaterial>, Without<MaterialCopy>>,
+    mut materials: ResMut<Assets<RDShaderMaterial>>,
+    kernel: Res<RDKernel>,
+) {
+    let Ok(handle) = mat_query.get_single() else { return; };
+    let Some(mat) = materials.get(handle) else { return; };
+    let model = RDModel::from_code(mat.model);
+    let mat = GrayScottParams::from_material(mat);
+    let explorer = &mut *explorer;
+    // Feed and kill picked without gliding
+    let mut picked = None;
+
+    egui::Window::new("RD Explorer").default_open(false).show(contexts.ctx_mut(), |ui| {
+        if model != RDModel::GrayScott {
+            ui.label("Only available for Gray-Scott");
+            return;
+        }
+
+        ui.horizontal(|ui| {
+            ui.label("Kill");
+            let (mut start, mut end) = explorer.kill.clone().into_inner();
+            ui.add(egui::DragValue::new(&mut start).speed(0.001).clamp_range(0.0..=end - 0.001));
+            ui.add(egui::DragValue::new(&mut end).speed(0.001).clamp_range(start + 0.001..=0.2));
+            explorer.kill = start..=end;
+            ui.label("Feed");
+            let (mut start, mut end) = explorer.feed.clone().into_inner();
+            ui.add(egui::DragValue::new(&mut start).speed(0.001).clamp_range(0.0..=end - 0.001));
+            ui.add(egui::DragValue::new(&mut end).speed(0.001).clamp_range(start + 0.001..=0.2));
+            explorer.feed = start..=end;
+        });
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut explorer.glide, "Glide");
+            ui.add_enabled(explorer.glide, egui::Slider::new(&mut explorer.glide_time, 0.1..=10.0).suffix(" s"));
+        });
+
+        explorer.render_thumbnails(mat.da, mat.db, kernel.weights());
+        let texture = match &explorer.texture {
+            Some(texture) => texture.clone(),
+            None => {
+                let texture = ui.ctx().load_texture("rd explorer", explorer.atlas.clone(), egui::TextureOptions::LINEAR);
+                explorer.texture = Some(texture.clone());
+                texture
+            }
+        };
+
+        let (response, painter) = ui.allocate_painter(egui::vec2(MAP_SIZE, MAP_SIZE), egui::Sense::click_and_drag());
+        let rect = response.rect;
+        painter.image(texture.id(), rect, egui::Rect::from_min_max(egui::pos2(0., 0.), egui::pos2(1., 1.)), egui::Color32::WHITE);
+
+        let cell_size = rect.width() / GRID as f32;
+        for (cell, outcome) in explorer.outcomes.iter().enumerate() {
+            if *outcome != Some(Outcome::Explodes) { continue; }
+            let min = rect.min + egui::vec2((cell % GRID) as f32, (cell / GRID) as f32) * cell_size;
+            painter.rect_filled(egui::Rect::from_min_size(min, egui::vec2(cell_size, cell_size)), 0., egui::Color32::from_rgba_unmultiplied(255, 0, 0, 60));
+        }
+
+        for (name, feed, kill) in REGIMES {
+            let pos = explorer.to_screen(rect, feed, kill);
+            if !rect.contains(pos) { continue; }
+            painter.circle_filled(pos, 3., egui::Color32::LIGHT_BLUE);
+            painter.text(pos + egui::vec2(5., 0.), egui::Align2::LEFT_CENTER, name, egui::FontId::proportional(12.), egui::Color32::LIGHT_BLUE);
+        }
+
+        if let Some(glide) = &explorer.active_glide {
+            painter.circle_stroke(explorer.to_screen(rect, glide.to.x, glide.to.y), 6., egui::Stroke::new(1., egui::Color32::YELLOW));
+        }
+        painter.circle_stroke(explorer.to_screen(rect, mat.feed, mat.kill), 6., egui::Stroke::new(2., egui::Color32::YELLOW));
+
+        if let Some(pos) = response.hover_pos() {
+            let (feed, kill) = explorer.from_screen(rect, pos);
+            ui.label(format!("Feed {:.4}, kill {:.4}", feed, kill));
+        } else {
+            ui.label(format!("Feed {:.4}, kill {:.4}", mat.feed, mat.kill));
+        }
+
+        if let Some(pos) = response.interact_pointer_pos().filter(|_| response.clicked() || response.dragged()) {
+            let (feed, kill) = explorer.from_screen(rect, pos.clamp(rect.min, rect.max));
+            match explorer.glide && !response.dragged() {
+                true => explorer.active_glide = Some(Glide {
+                    from: Vec2::new(mat.feed, mat.kill),
+                    to: Vec2::new(feed, kill),
+                    elapsed: 0.,
+                    duration: explorer.glide_time,
+                }),
+                false => {
+                    explorer.active_glide = None;
+                    picked = Some((feed, kill));
+                }
+            }
+        }
+    });
+
+    // Every mutable access rebuilds the bind group, so only picks take the material
+    if let Some((feed, kill)) = picked {
+        if let Some(mat) = materials.get_mut(handle) {
+            mat.feed = feed;
+            mat.kill = kill;
+        }
+    }
+}
+
+pub fn rd_explorer_glide_system(
+    mut explorer: ResMut<RDExplorer>,
+    mat_query: Query<&Handle<RDShaderMaterial>, Without<MaterialCopy>>,
+    mut materials: ResMut<Assets<RDShaderMaterial>>,
+    time: Res<Time>,
+) {
+    let Some(glide) = &mut explorer.active_glide else { return; };
+    let Some(mat) = mat_query.get_single().ok().and_then(|h| materials.get_mut(h)) else { return; };
+
+    glide.elapsed += time.delta_seconds();
+    let t = (glide.elapsed / glide.duration).clamp(0., 1.);
+    let eased = t * t * (3. - 2. * t);
+    let value = glide.from.lerp(glide.to, eased);
+    mat.feed = value.x;
+    mat.kill = value.y;
+    if t >= 1. {
+        explorer.active_glide = None;
+    }
+}
@@ -0,0 +1,268 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use serde::{Deserialize, Serialize};
+use crate::midi::{midi_learn_button, MidiMappings};
+use crate::osc::{MaterialCopy, OscParam};
+use crate::rd::RDShaderMaterial;
+
+/// Weights of the taps around a cell, `[y][x]` with the cell in the middle
+pub type Kernel = [[f32; 5]; 5];
+
+/// Separable weights of the blurred 5x5 kernel `rd.wgsl` was tuned with
+const SMOOTH_WEIGHTS: [f32; 5] = [0.2, 0.8, 1.0, 0.8, 0.2];
+/// Scale of the blurred kernel, the other shapes diffuse as fast as it does
+const SMOOTH_SCALE: f32 = 0.25;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[repr(usize)]
+pub enum KernelShape {
+    /// Blurred 5x5, the original kernel
+    #[default]
+    Smooth,
+    /// 3x3 with the direct neighbours only
+    Cross,
+    /// 3x3 with the diagonals
+    Box,
+    /// Diffuses faster along `angle`
+    Anisotropic,
+}
+
+impl KernelShape {
+    pub const ALL: [KernelShape; 4] = [KernelShape::Smooth, KernelShape::Cross, KernelShape::Box, KernelShape::Anisotropic];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            KernelShape::Smooth => "Smooth 5x5",
+            KernelShape::Cross => "Cross 3x3",
+            KernelShape::Box => "Box 3x3",
+            KernelShape::Anisotropic => "Anisotropic",
+        }
+    }
+
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+/// Laplacian used by the diffusion models, uploaded to the material whenever it changes
+#[derive(Resource, Clone, Serialize, Deserialize)]
+pub struct RDKernel {
+    pub shape: KernelShape,
+    /// Direction of anisotropy and drift in degrees
+    pub angle: f32,
+    /// 0 is isotropic, towards 1 diffusion only happens along `angle`
+    pub anisotropy: f32,
+    /// Moves the pattern along `angle`
+    pub drift: f32,
+}
+
+impl Default for RDKernel {
+    fn default() -> Self {
+        Self { shape: KernelShape::Smooth, angle: 0., anisotropy: 0.5, drift: 0. }
+    }
+}
+
+impl RDKernel {
+    pub fn weights(&self) -> Kernel {
+        let mut kernel = [[0.; 5]; 5];
+        let mut set = |x: isize, y: isize, w: f32| kernel[(y + 2) as usize][(x + 2) as usize] += w;
+        match self.shape {
+            KernelShape::Smooth => {
+                for (y, wy) in SMOOTH_WEIGHTS.iter().enumerate() {
+                    for (x, wx) in SMOOTH_WEIGHTS.iter().enumerate() {
+                        set(x as isize - 2, y as isize - 2, wx * wy);
+                    }
+                }
+            }
+            KernelShape::Cross => {
+                for (x, y) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+                    set(x, y, 1.);
+                }
+            }
+            KernelShape::Box => {
+                for (x, y) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+                    set(x, y, 0.2);
+                }
+                for (x, y) in [(1, 1), (-1, 1), (1, -1), (-1, -1)] {
+                    set(x, y, 0.05);
+                }
+            }
+            KernelShape::Anisotropic => {
+                // Diffusion tensor rotated by angle, second derivatives by central differences
+                let (sin, cos) = self.angle.to_radians().sin_cos();
+                let (major, minor) = (1. + self.anisotropy.clamp(0., 0.95), 1. - self.anisotropy.clamp(0., 0.95));
+                let dxx = major * cos * cos + minor * sin * sin;
+                let dyy = major * sin * sin + minor * cos * cos;
+                let dxy = (major - minor) * sin * cos;
+                set(1, 0, dxx);
+                set(-1, 0, dxx);
+                set(0, 1, dyy);
+                set(0, -1, dyy);
+                for (x, y) in [(1, 1), (-1, -1)] {
+                    set(x, y, dxy / 2.);
+                }
+                for (x, y) in [(1, -1), (-1, 1)] {
+                    set(x, y, -dxy / 2.);
+                }
+            }
+        }
+        kernel[2][2] = 0.;
+
+        // Same response to a parabola as the smooth kernel, so diffusion rates mean the same with every shape
+        let scale = match self.shape {
+            KernelShape::Smooth => SMOOTH_SCALE,
+            _ => smooth_response() / response(&kernel),
+        };
+        for w in kernel.iter_mut().flatten() {
+            *w *= scale;
+        }
+        kernel[2][2] = -kernel.iter().flatten().sum::<f32>();
+
+        // Central differences, they sum up to zero
+        let (sin, cos) = self.angle.to_radians().sin_cos();
+        let drift = self.drift * 0.5;
+        kernel[2][1] += drift * cos;
+        kernel[2][3] -= drift * cos;
+        kernel[1][2] += drift * sin;
+        kernel[3][2] -= drift * sin;
+
+        kernel
+    }
+
+    /// `uniform_laplace` in `rd.wgsl`, the 25 taps packed into vec4s
+    pub fn uniform(&self) -> [Vec4; 7] {
+        let mut packed = [0.; 28];
+        for (i, w) in self.weights().iter().flatten().enumerate() {
+            packed[i] = *w;
+        }
+        let mut uniform = [Vec4::ZERO; 7];
+        for (i, v) in uniform.iter_mut().enumerate() {
+            *v = Vec4::from_slice(&packed[i * 4..i * 4 + 4]);
+        }
+        uniform
+    }
+}
+
+/// Mean of what the kernel gives for x² and y², ignoring the scale
+fn response(kernel: &Kernel) -> f32 {
+    let mut sum = 0.;
+    for (y, row) in kernel.iter().enumerate() {
+        for (x, w) in row.iter().enumerate() {
+            let (dx, dy) = (x as f32 - 2., y as f32 - 2.);
+            sum += w * (dx * dx + dy * dy) / 2.;
+        }
+    }
+    sum
+}
+
+fn smooth_response() -> f32 {
+    response(&RDKernel { shape: KernelShape::Smooth, ..default() }.weights())
+}
+
+/// Uploads the kernel when it changed
+pub fn rd_kernel_system(
+    kernel: Res<RDKernel>,
+    mat_query: Query<&Handle<RDShaderMaterial>, Without<MaterialCopy>>,
+    mut materials: ResMut<Assets<RDShaderMaterial>>,
+) {
+    if !kernel.is_changed() { return; }
+    let Some(mat) = mat_query.get_single().ok().and_then(|h| materials.get_mut(h)) else { return; };
+    mat.laplace = kernel.uniform();
+}
+
+pub fn rd_kernel_ui(
+    mut contexts: EguiContexts,
+    mut kernel: ResMut<RDKernel>,
+    mut midi: ResMut<MidiMappings>,
+) {
+    egui::Window::new("RD Kernel").default_open(false).show(contexts.ctx_mut(), |ui| {
+        let mut shape = kernel.shape;
+        egui::ComboBox::from_label("Shape")
+            .selected_text(shape.name())
+            .show_ui(ui, |ui| {
+                for s in KernelShape::ALL {
+                    ui.selectable_value(&mut shape, s, s.name());
+                }
+            });
+        if shape != kernel.shape {
+            kernel.shape = shape;
+        }
+
+        // Only touch the resource on edits, every change re-uploads the kernel
+        let (mut angle, mut anisotropy, mut drift) = (kernel.angle, kernel.anisotropy, kernel.drift);
+        egui::Grid::new("rd kernel")
+            .num_columns(3)
+            .spacing([40.0, 4.0])
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label("Angle");
+                ui.add(egui::Slider::new(&mut angle, 0.0..=360.0));
+                midi_learn_button(ui, &mut midi, "rd/kernel/angle");
+                ui.end_row();
+
+                ui.label("Anisotropy");
+                ui.add_enabled(shape == KernelShape::Anisotropic, egui::Slider::new(&mut anisotropy, 0.0..=0.95));
+                midi_learn_button(ui, &mut midi, "rd/kernel/anisotropy");
+                ui.end_row();
+
+                ui.label("Drift");
+                ui.add(egui::Slider::new(&mut drift, 0.0..=0.5));
+                midi_learn_button(ui, &mut midi, "rd/kernel/drift");
+                ui.end_row();
+            });
+        if (angle, anisotropy, drift) != (kernel.angle, kernel.anisotropy, kernel.drift) {
+            kernel.angle = angle;
+            kernel.anisotropy = anisotropy;
+            kernel.drift = drift;
+        }
+
+        ui.separator();
+        egui::Grid::new("rd kernel weights").show(ui, |ui| {
+            for row in kernel.weights() {
+                for w in row {
+                    ui.monospace(format!("{:6.3}", w));
+                }
+                ui.end_row();
+            }
+        });
+    });
+}
+
+/// `/vj/rd/kernel/...`
+pub fn kernel_osc_params() -> Vec<OscParam> {
+    vec![
+        OscParam::resource_int::<RDKernel>("rd/kernel/shape", "Laplacian kernel, 0 smooth, 1 cross, 2 box, 3 anisotropic",
+            0..=KernelShape::ALL.len() as i32 - 1, |k| k.shape.index() as i32, |k, v| k.shape = KernelShape::ALL[v as usize]),
+        OscParam::resource::<RDKernel>("rd/kernel/angle", "Direction of anisotropy and drift in degrees", 0. ..=360., |k| k.angle, |k, v| k.angle = v),
+        OscParam::resource::<RDKernel>("rd/kernel/anisotropy", "Diffusion along the angle over across it", 0. ..=0.95, |k| k.anisotropy, |k, v| k.anisotropy = v),
+        OscParam::resource::<RDKernel>("rd/kernel/drift", "Moves the pattern along the angle", 0. ..=0.5, |k| k.drift, |k, v| k.drift = v),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn smooth_weights() {
+        let kernel = RDKernel::default().weights();
+        for (y, row) in kernel.iter().enumerate() {
+            for (x, w) in row.iter().enumerate() {
+                let expected = match (x, y) {
+                    (2, 2) => -2.,
+                    _ => 0.25 * SMOOTH_WEIGHTS[x] * SMOOTH_WEIGHTS[y],
+                };
+                assert!((w - expected).abs() < 1e-6, "weight at ({x}, {y}) is {w}, expected {expected}");
+            }
+        }
+    }
+
+    #[test]
+    fn weights_sum_to_zero() {
+        for shape in KernelShape::ALL {
+            let kernel = RDKernel { shape, angle: 30., anisotropy: 0.5, drift: 0. };
+            let sum: f32 = kernel.weights().iter().flatten().sum();
+            assert!(sum.abs() < 1e-5, "{} sums to {sum}", shape.name());
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use std::path::Path;
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use crate::midi::{midi_learn_button, MidiMappings};
+use crate::osc::MaterialCopy;
+use crate::rd::RDShaderMaterial;
+use crate::shared_textures::SharedTextures;
+use crate::status::StatusPanel;
+
+/// Values of `map_mode` in `rd.wgsl`
+const MAP_MODE_OFF: u32 = 0;
+const MAP_MODE_RADIAL: u32 = 1;
+const MAP_MODE_TEXTURE: u32 = 2;
+
+/// Texture that modulates feed, kill and diffusion rates across the field
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(usize)]
+pub enum MapSource {
+    Off,
+    /// Rings around the center
+    Radial,
+    /// Image from the assets folder
+    Image,
+    Fractal,
+    ChipSpin,
+    Feedback,
+}
+
+impl MapSource {
+    pub const ALL: [MapSource; 6] = [
+        MapSource::Off, MapSource::Radial, MapSource::Image,
+        MapSource::Fractal, MapSource::ChipSpin, MapSource::Feedback,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            MapSource::Off => "Off",
+            MapSource::Radial => "Radial",
+            MapSource::Image => "Image",
+            MapSource::Fractal => "Fractal",
+            MapSource::ChipSpin => "Chip Spin",
+            MapSource::Feedback => "Feedback",
+        }
+    }
+
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+/// Selected map. How strongly it affects each parameter is part of the material.
+#[derive(Resource)]
+pub struct RDMap {
+    pub source: MapSource,
+    /// Used by `MapSource::Image`, relative to the assets folder
+    pub image_path: String,
+    image: Handle<Image>,
+}
+
+impl FromWorld for RDMap {
+    fn from_world(_world: &mut World) -> Self {
+        Self {
+            source: MapSource::Radial,
+            image_path: "images/plakat_scaled_clean.png".to_owned(),
+            image: Handle::default(),
+        }
+    }
+}
+
+/// Points the material at the selected map
+pub fn rd_map_system(
+    mut map: ResMut<RDMap>,
+    mat_query: Query<&Handle<RDShaderMaterial>, Without<MaterialCopy>>,
+    mut materials: ResMut<Assets<RDShaderMaterial>>,
+    shared_textures: Res<SharedTextures>,
+    asset_server: Res<AssetServer>,
+    mut status: ResMut<StatusPanel>,
+) {
+    if !map.is_changed() { return; }
+    let Some(mat) = mat_query.get_single().ok().and_then(|h| materials.get_mut(h)) else { return; };
+
+    let map = &mut *map;
+    status.resolve("rd_map");
+    let (mode, texture) = match map.source {
+        MapSource::Off => (MAP_MODE_OFF, shared_textures.placeholder.clone()),
+        MapSource::Radial => (MAP_MODE_RADIAL, shared_textures.placeholder.clone()),
+        MapSource::Image if asset_server.asset_io().is_file(Path::new(&map.image_path)) => {
+            map.image = asset_server.load(map.image_path.as_str());
+            (MAP_MODE_TEXTURE, map.image.clone())
+        }
+        MapSource::Image => {
+            status.warn("rd_map", format!("RD map {} not found", map.image_path));
+            (MAP_MODE_OFF, shared_textures.placeholder.clone())
+        }
+        MapSource::Fractal => (MAP_MODE_TEXTURE, shared_textures.fractal.clone()),
+        MapSource::ChipSpin => (MAP_MODE_TEXTURE, shared_textures.chip_spin.clone()),
+        MapSource::Feedback => (MAP_MODE_TEXTURE, shared_textures.feedback.clone()),
+    };
+    mat.map_mode = mode;
+    mat.map = texture;
+}
+
+pub fn rd_map_ui(
+    mut contexts: EguiContexts,
+    mut map: ResMut<RDMap>,
+    mat_query: Query<&Handle<RDShaderMaterial>, Without<MaterialCopy>>,
+    mut materials: ResMut<Assets<RDShaderMaterial>>,
+    mut midi: ResMut<MidiMappings>,
+    // Text in the path field, and the map path it was last reset to
+    mut image_path: Local<(String, String)>,
+) {
+    let Ok(handle) = mat_query.get_single() else { return; };
+    let Some(before) = materials.get(handle).map(|m| m.map_strength) else { return; };
+    let mut strength = before;
+
+    egui::Window::new("RD Map").default_open(false).show(contexts.ctx_mut(), |ui| {
+        let mut source = map.source;
+        egui::ComboBox::from_label("Source")
+            .selected_text(source.name())
+            .show_ui(ui, |ui| {
+                for s in MapSource::ALL {
+                    ui.selectable_value(&mut source, s, s.name());
+                }
+            });
+        if source != map.source {
+            map.source = source;
+        }
+
+        if map.source == MapSource::Image {
+            let (path, synced) = &mut *image_path;
+            // Presets, autosave restores and OSC change the path too
+            if *synced != map.image_path {
+                *synced = map.image_path.clone();
+                *path = map.image_path.clone();
+            }
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(path);
+                if ui.button("Load").clicked() {
+                    map.image_path = path.clone();
+                }
+            });
+        }
+
+        ui.separator();
+
+        ui.label("Strength");
+        egui::Grid::new("rd map strength")
+            .num_columns(3)
+            .spacing([40.0, 4.0])
+            .striped(true)
+            .show(ui, |ui| {
+                let rows = [
+                    ("Feed", "rd/map/feed", 0.001),
+                    ("Kill", "rd/map/kill", 0.001),
+                    ("Da", "rd/map/da", 0.01),
+                    ("Db", "rd/map/db", 0.01),
+                ];
+                for (i, (label, path, speed)) in rows.into_iter().enumerate() {
+                    ui.label(label);
+                    ui.add(egui::DragValue::new(&mut strength[i]).speed(speed).max_decimals(4));
+                    midi_learn_button(ui, &mut midi, path);
+                    ui.end_row();
+                }
+            });
+    });
+
+    // Every mutable access rebuilds the bind group, so only edits take the material
+    if strength != before {
+        if let Some(mat) = materials.get_mut(handle) {
+            mat.map_strength = strength;
+        }
+    }
+}
@@ -1,6 +1,16 @@
+pub mod actions;
+pub mod cpu;
+pub mod explorer;
+pub mod kernel;
+pub mod maps;
+pub mod models;
+pub mod paint;
+pub mod seeds;
+pub mod simulation;
 pub mod ui;
 pub mod wipes;
 
+use std::ops::RangeInclusive;
 use bevy::core_pipeline::clear_color::ClearColorConfig;
 use bevy::prelude::*;
 use bevy::reflect::TypeUuid;
@@ -12,73 +22,226 @@ use bevy::render::render_resource::{
 use bevy::render::texture::ImageSampler;
 use bevy::render::view::RenderLayers;
 
-use crate::fractal::FractalRenderTarget;
-use crate::rd::ui::{ui_system, WipeAutomationControls};
-use crate::rd::wipes::{wipe_event_listener_system, wipe_system, WipeEvent};
+use crate::autosave::{AddModuleState, ModuleState};
+use crate::config::AppConfig;
+use crate::keybindings::{AddKeyActions, KeyAction};
+use crate::osc::{arg_f32, arg_int, AddOscControls, MaterialCopy, OscAction, OscParam};
+use crate::osc::schema::{ArgSchema, ArgSpec};
+use crate::presets::{AddPresetBank, PresetModule};
+use crate::rd::actions::{action_key_actions, action_osc_actions, rd_action_system, RDAction, RDActionAutomation, RDActions};
+use crate::rd::cpu::{cpu_preview_ui, CpuPreview};
+use crate::rd::explorer::{rd_explorer_glide_system, rd_explorer_ui, RDExplorer};
+use crate::rd::paint::{paint_system, paint_ui, BrushControls};
+use crate::rd::kernel::{kernel_osc_params, rd_kernel_system, rd_kernel_ui, RDKernel};
+use crate::rd::models::{model_osc_params, rd_model_system, RDModel};
+use crate::rd::maps::{rd_map_system, rd_map_ui, MapSource, RDMap};
+use crate::rd::seeds::{seed_event_listener_system, seed_system, seed_ui, setup_seeds, SeedControls, SeedEvent, SeedSource};
+use crate::rd::simulation::{rd_step_system, rd_sync_back_material, spawn_rd_passes, RDSimulation};
+use crate::rd::ui::{ui_system, ManualWipeControls, WipeAutomationControls, WipePreset};
+use crate::rd::wipes::{wipe_event_listener_system, wipe_system, WipeEvent, WipeShape};
+use crate::shared_textures::SharedTextures;
 use bevy_pyree::render::{spawn_fs_quad, spawn_render_image_to_screen, FSQuad};
 use bevy_smud::SmudPlugin;
+use rosc::OscType;
+use serde::{Deserialize, Serialize};
 
 pub struct RDPlugin;
 
 impl Plugin for RDPlugin {
     fn build(&self, app: &mut App) {
+        // OSC ranges of positions in the wipe space
         app.add_plugin(SmudPlugin)
             .add_startup_system(spawn_rd)
             .add_plugin(MaterialPlugin::<RDShaderMaterial>::default())
             .add_asset::<RDShaderMaterial>()
             .register_asset_reflect::<RDShaderMaterial>()
             .add_system(ui_system)
+            .init_resource::<RDResolution>()
             .init_resource::<RDRenderTarget>()
+            .init_resource::<RDSimulation>()
+            .add_system(rd_resize_system)
+            .add_system(rd_step_system)
+            .add_system(rd_sync_back_material.in_base_set(CoreSet::PostUpdate))
+            .add_event::<RDAction>()
+            .init_resource::<RDActions>()
+            .init_resource::<RDActionAutomation>()
+            .add_system(rd_action_system.before(rd_step_system))
+            .add_system(rd_model_system.before(rd_action_system))
+            .init_resource::<CpuPreview>()
+            .add_system(cpu_preview_ui)
+            .init_resource::<RDExplorer>()
+            .add_system(rd_explorer_ui)
+            .add_system(rd_explorer_glide_system)
+            .init_resource::<RDKernel>()
+            .add_system(rd_kernel_ui)
+            .add_system(rd_kernel_system.after(rd_kernel_ui))
+            .init_resource::<BrushControls>()
+            .add_system(paint_ui)
+            .add_system(paint_system.after(paint_ui))
+            .init_resource::<RDMap>()
+            .add_system(rd_map_ui)
+            .add_system(rd_map_system.after(rd_map_ui))
+            .add_event::<SeedEvent>()
+            .init_resource::<SeedControls>()
+            .add_module_state::<SeedControls>()
+            .add_startup_system(setup_seeds)
+            .add_system(seed_ui)
+            .add_system(seed_event_listener_system)
+            .add_system(seed_system)
             .add_event::<WipeEvent>()
             .add_system(wipe_event_listener_system)
             .add_system(wipe_system)
             .init_resource::<WipeAutomationControls>()
+            .init_resource::<ManualWipeControls>()
+            .add_preset_bank::<RDPreset>(PresetModule::RD, vec![
+                ("Rings", RDPreset::gray_scott(1., 0.3, 0.0287, 0.078)),
+                ("Fuzzy", RDPreset::gray_scott(1., 0.07, 0.037, 0.130)),
+                ("FHN Stripes", RDPreset::model(RDModel::FitzHughNagumo, RDModel::FitzHughNagumo.defaults())),
+                ("FHN Spots", RDPreset::model(RDModel::FitzHughNagumo, [1., 8., 0.2, -0.1])),
+                ("Brusselator", RDPreset::model(RDModel::Brusselator, RDModel::Brusselator.defaults())),
+                ("Cyclic", RDPreset::model(RDModel::Cyclic, RDModel::Cyclic.defaults())),
+                ("Lenia", RDPreset::model(RDModel::Lenia, RDModel::Lenia.defaults())),
+                // Radius 1 runs the Game of Life rules, mu, sigma and dt are ignored
+                ("Life", RDPreset::model(RDModel::Lenia, { let mut p = RDModel::Lenia.defaults(); p[0] = 1.; p })),
+            ])
+            .add_osc_params(osc_params())
+            .add_osc_actions(vec![OscAction::new(
+                "wipe/trigger", "Trigger a wipe with the manual wipe settings. Shape by name or index, steps are optional",
+                "si", wipe_trigger,
+            ).with_schema(ArgSchema::new(vec![
+                ArgSpec::any("shape").optional(),
+                ArgSpec::int("steps", 1..=20).optional(),
+            ]))])
+            .add_osc_actions(vec![OscAction::new(
+                "rd/seed", "Stamp the selected seed into the field, at the given position and scale if set", "fff",
+                |world, args| {
+                    let resolution = *world.resource::<RDResolution>();
+                    let mut event = world.get_resource::<SeedControls>().ok_or("seeds not available")?.event(&resolution);
+                    if let (Some(x), Some(y)) = (args.get(0).and_then(arg_f32), args.get(1).and_then(arg_f32)) {
+                        let x_range = resolution.wipe_x_range();
+                        event.position = Vec2::new(x.clamp(*x_range.start(), *x_range.end()), y);
+                    }
+                    if let Some(scale) = args.get(2).and_then(arg_f32) {
+                        event.scale = scale;
+                    }
+                    world.resource_mut::<Events<SeedEvent>>().send(event);
+                    Ok(())
+                },
+            ).with_schema(ArgSchema::new(vec![
+                ArgSpec::float_unclamped("x").optional(),
+                ArgSpec::float("y", -50. ..=50.).optional(),
+                ArgSpec::float("scale", 0.1..=5.).optional(),
+            ]))])
+            .add_key_actions(wipe_key_actions())
+            .add_osc_actions(action_osc_actions())
+            .add_key_actions(action_key_actions())
+            .add_key_actions(vec![KeyAction::trigger("rd_seed", "Seed the reaction-diffusion field", Some("7"), |world| {
+                let resolution = *world.resource::<RDResolution>();
+                let Some(controls) = world.get_resource::<SeedControls>() else { return; };
+                let event = controls.event(&resolution);
+                world.resource_mut::<Events<SeedEvent>>().send(event);
+            })])
         ;
+
+        let default_wipes = WipePreset::capture(&mut app.world).unwrap();
+        app.add_preset_bank::<WipePreset>(PresetModule::Wipes, vec![("Default", default_wipes)]);
+    }
+}
+
+/// Size of the simulation. Changing it reallocates the render target, which clears it.
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct RDResolution {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl FromWorld for RDResolution {
+    fn from_world(world: &mut World) -> Self {
+        let config = world.get_resource::<AppConfig>().map(|c| c.rd.clone()).unwrap_or_default();
+        Self { width: config.width, height: config.height }
+    }
+}
+
+impl RDResolution {
+    pub fn extent(&self) -> Extent3d {
+        Extent3d { width: self.width, height: self.height, ..default() }
+    }
+
+    pub fn as_vec2(&self) -> Vec2 {
+        Vec2::new(self.width as f32, self.height as f32)
+    }
+
+    pub fn aspect(&self) -> f32 {
+        self.width as f32 / self.height as f32
+    }
+
+    /// Wipes are drawn in a 100 units high area, as wide as the aspect ratio makes it
+    pub fn wipe_width(&self) -> f32 {
+        100. * self.aspect()
+    }
+
+    /// Left to right edge of the wipe space
+    pub fn wipe_x_range(&self) -> RangeInclusive<f32> {
+        -self.wipe_width() / 2. ..=self.wipe_width() / 2.
     }
 }
 
+fn wipe_projection(resolution: &RDResolution) -> ScalingMode {
+    ScalingMode::Fixed {
+        width: resolution.wipe_width(),
+        height: 100.,
+    }
+}
+
+#[derive(Component)]
+pub struct WipeCamera;
+
+/// The simulation ping-pongs between two buffers. A frame starts from the front one,
+/// the one it ends in is shared with the other modules.
 #[derive(Resource)]
 pub struct RDRenderTarget {
     pub render_target: Handle<Image>,
+    pub back_buffer: Handle<Image>,
 }
 
 impl FromWorld for RDRenderTarget {
     fn from_world(world: &mut World) -> Self {
+        let size = world.resource::<RDResolution>().extent();
         let mut images = world.get_resource_mut::<Assets<Image>>().unwrap();
+        let render_target = images.add(rd_image(size));
+        let back_buffer = images.add(rd_image(size));
 
-        let size = Extent3d {
-            width: 1024,
-            height: 1024,
-            ..default()
-        };
-        let mut image = Image {
-            texture_descriptor: TextureDescriptor {
-                label: None,
-                size,
-                dimension: TextureDimension::D2,
-                format: TextureFormat::Rgba16Float,
-                mip_level_count: 1,
-                sample_count: 1,
-                usage: TextureUsages::TEXTURE_BINDING
-                    | TextureUsages::COPY_DST
-                    | TextureUsages::RENDER_ATTACHMENT,
-                view_formats: &[],
-            },
-            sampler_descriptor: ImageSampler::Descriptor(SamplerDescriptor {
-                address_mode_u: AddressMode::Repeat,
-                address_mode_v: AddressMode::Repeat,
-                ..Default::default()
-            }),
-            ..default()
-        };
-        image.resize(size);
+        world.resource_mut::<SharedTextures>().rd = render_target.clone();
 
-        Self {
-            render_target: images.add(image),
-        }
+        Self { render_target, back_buffer }
     }
 }
 
+fn rd_image(size: Extent3d) -> Image {
+    let mut image = Image {
+        texture_descriptor: TextureDescriptor {
+            label: None,
+            size,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Rgba16Float,
+            mip_level_count: 1,
+            sample_count: 1,
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_DST
+                | TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        },
+        sampler_descriptor: ImageSampler::Descriptor(SamplerDescriptor {
+            address_mode_u: AddressMode::Repeat,
+            address_mode_v: AddressMode::Repeat,
+            ..Default::default()
+        }),
+        ..default()
+    };
+    image.resize(size);
+    image
+}
+
 #[derive(AsBindGroup, TypeUuid, Clone, Reflect, FromReflect)]
 #[uuid = "7a1722d8-d8a7-4166-96d2-646197a02bfe"]
 pub struct RDShaderMaterial {
@@ -93,6 +256,37 @@ pub struct RDShaderMaterial {
     pub feed: f32,
     #[uniform(5)]
     pub kill: f32,
+    #[uniform(6)]
+    pub resolution: Vec2,
+    /// 0 off, 1 radial, 2 texture
+    #[uniform(7)]
+    pub map_mode: u32,
+    /// How much the map changes feed, kill, Da and Db
+    #[uniform(8)]
+    pub map_strength: Vec4,
+    #[texture(9)]
+    #[sampler(10)]
+    pub map: Handle<Image>,
+    /// `RDAction` to run instead of a step, 0 for none
+    #[uniform(11)]
+    pub action: u32,
+    /// Random seed and noise density
+    #[uniform(12)]
+    pub action_params: Vec4,
+    /// `RDModel::code`, Gray-Scott uses da, db, feed and kill
+    #[uniform(13)]
+    pub model: u32,
+    #[uniform(14)]
+    pub fhn: Vec4,
+    #[uniform(15)]
+    pub brusselator: Vec4,
+    #[uniform(16)]
+    pub cyclic: Vec4,
+    #[uniform(17)]
+    pub lenia: Vec4,
+    /// `RDKernel::uniform`
+    #[uniform(18)]
+    pub laplace: [Vec4; 7],
 }
 
 impl Material for RDShaderMaterial {
@@ -101,29 +295,181 @@ impl Material for RDShaderMaterial {
     }
 }
 
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RDPreset {
+    pub da: f32,
+    pub db: f32,
+    pub feed: f32,
+    pub kill: f32,
+    #[serde(default)]
+    pub model: RDModel,
+    /// Parameters of `model` unless it's Gray-Scott
+    #[serde(default)]
+    pub model_params: Option<[f32; 4]>,
+    #[serde(default)]
+    pub kernel: RDKernel,
+}
+
+impl RDPreset {
+    fn gray_scott(da: f32, db: f32, feed: f32, kill: f32) -> Self {
+        Self { da, db, feed, kill, model: RDModel::GrayScott, model_params: None, kernel: RDKernel::default() }
+    }
+
+    /// Keeps the default Gray-Scott parameters for when the model is switched back
+    fn model(model: RDModel, params: [f32; 4]) -> Self {
+        let [da, db, feed, kill] = RDModel::GrayScott.defaults();
+        Self { da, db, feed, kill, model, model_params: Some(params), kernel: RDKernel::default() }
+    }
+}
+
+impl ModuleState for RDPreset {
+    const KEY: &'static str = "rd";
+
+    fn capture(world: &mut World) -> Option<Self> {
+        let handle = world.query_filtered::<&Handle<RDShaderMaterial>, Without<MaterialCopy>>().get_single(world).ok()?.clone();
+        let mat = world.resource::<Assets<RDShaderMaterial>>().get(&handle)?;
+        let model = RDModel::from_code(mat.model);
+        let model_params = (model != RDModel::GrayScott).then(|| model.values(mat));
+        let kernel = world.resource::<RDKernel>().clone();
+        Some(Self { da: mat.da, db: mat.db, feed: mat.feed, kill: mat.kill, model, model_params, kernel })
+    }
+
+    fn apply(&self, world: &mut World) {
+        // Uploaded to the material by rd_kernel_system
+        *world.resource_mut::<RDKernel>() = self.kernel.clone();
+        let Ok(handle) = world.query_filtered::<&Handle<RDShaderMaterial>, Without<MaterialCopy>>().get_single(world).map(|h| h.clone()) else { return; };
+        let mut materials = world.resource_mut::<Assets<RDShaderMaterial>>();
+        let Some(mat) = materials.get_mut(&handle) else { return; };
+        mat.da = self.da;
+        mat.db = self.db;
+        mat.feed = self.feed;
+        mat.kill = self.kill;
+        mat.model = self.model.code();
+        if let Some(params) = self.model_params {
+            for (value, param) in self.model.values_mut(mat).into_iter().zip(params) {
+                *value = param;
+            }
+        }
+    }
+}
+
+/// `/vj/rd/...` and `/vj/wipe/...`
+fn osc_params() -> Vec<OscParam> {
+    model_osc_params().into_iter().chain(kernel_osc_params()).chain([
+        OscParam::resource_int::<RDMap>("rd/map/source", "Map modulating the parameters, 0 off, 1 radial, 2 image, 3 fractal, 4 chip spin, 5 feedback",
+            0..=MapSource::ALL.len() as i32 - 1, |m| m.source.index() as i32, |m, v| m.source = MapSource::ALL[v as usize]),
+        OscParam::material::<RDShaderMaterial>("rd/map/feed", "Feed change at full map", -0.05..=0.05, |m| m.map_strength.x, |m, v| m.map_strength.x = v),
+        OscParam::material::<RDShaderMaterial>("rd/map/kill", "Kill change at full map", -0.05..=0.05, |m| m.map_strength.y, |m, v| m.map_strength.y = v),
+        OscParam::material::<RDShaderMaterial>("rd/map/da", "Relative Da change at full map", -1. ..=1., |m| m.map_strength.z, |m, v| m.map_strength.z = v),
+        OscParam::material::<RDShaderMaterial>("rd/map/db", "Relative Db change at full map", -1. ..=1., |m| m.map_strength.w, |m, v| m.map_strength.w = v),
+        OscParam::resource_int::<SeedControls>("rd/seed/source", "Seed source, 0 image, 1 text, 2 chip spin",
+            0..=SeedSource::ALL.len() as i32 - 1, |c| c.source.index() as i32, |c, v| c.source = SeedSource::ALL[v as usize]),
+        OscParam::resource::<SeedControls>("rd/seed/x", "Seed position", -50. ..=50., |c| c.position[0], |c, v| c.position[0] = v)
+            .with_current_range(|world| world.get_resource::<RDResolution>().map(|r| r.wipe_x_range())),
+        OscParam::resource::<SeedControls>("rd/seed/y", "Seed position", -50. ..=50., |c| c.position[1], |c, v| c.position[1] = v),
+        OscParam::resource::<SeedControls>("rd/seed/scale", "Seed size", 0.1..=5., |c| c.scale, |c, v| c.scale = v),
+        OscParam::resource_bool::<SeedControls>("rd/seed/on_beat", "Seed on beat", |c| c.on_beat, |c, v| c.on_beat = v),
+        OscParam::resource_int::<RDActionAutomation>("rd/action/on_beat", "Action run on beat, 0 off, 1 clear, 2 noise, 3 invert",
+            0..=RDAction::ALL.len() as i32, |a| a.action.map_or(0, |a| a.index() as i32 + 1),
+            |a, v| a.action = (v > 0).then(|| RDAction::ALL[v as usize - 1])),
+        OscParam::resource_int::<RDActionAutomation>("rd/action/beat_div", "Beats between automated actions", 1..=64,
+            |a| a.beat_div as i32, |a, v| a.beat_div = v as usize),
+        OscParam::resource::<RDActions>("rd/noise_density", "Share of the field covered by /vj/rd/noise", 0. ..=1., |a| a.noise_density, |a, v| a.noise_density = v),
+        OscParam::resource::<RDSimulation>("rd/speed", "Simulation speed, 1 is rd.steps_per_second", 0. ..=4., |s| s.speed, |s, v| s.speed = v),
+
+        OscParam::resource_int::<ManualWipeControls>(
+            "wipe/shape", "Shape of manual wipes, 0: circle, 1: octagon, 2: cross, 3: square, 4: hexagram", 0..=4,
+            |c| c.event.shape.index() as i32, |c, v| c.event.shape = WipeShape::ALL[v as usize].clone(),
+        ),
+        OscParam::resource::<ManualWipeControls>("wipe/duration", "Duration of manual wipes", 0. ..=2., |c| c.event.duration, |c, v| c.event.duration = v),
+        OscParam::resource_int::<ManualWipeControls>("wipe/steps", "Steps of manual wipes", 1..=20, |c| c.event.steps as i32, |c, v| c.event.steps = v as isize),
+        OscParam::resource::<ManualWipeControls>("wipe/start_size", "Start size of manual wipes", 1. ..=100., |c| c.event.start_size, |c, v| c.event.start_size = v),
+        OscParam::resource::<ManualWipeControls>("wipe/end_size", "End size of manual wipes", 1. ..=100., |c| c.event.end_size, |c, v| c.event.end_size = v),
+
+        OscParam::resource_bool::<WipeAutomationControls>("wipe/auto/on_beat", "Wipe on beat", |c| c.on_beat, |c, v| c.on_beat = v),
+        OscParam::resource_bool::<WipeAutomationControls>("wipe/auto/rand_shape", "Random shape", |c| c.randomize_shape, |c, v| c.randomize_shape = v),
+        OscParam::resource_bool::<WipeAutomationControls>("wipe/auto/rand_steps", "Random steps", |c| c.randomize_steps, |c, v| c.randomize_steps = v),
+        OscParam::resource_bool::<WipeAutomationControls>("wipe/auto/rand_min", "Random start size", |c| c.randomize_min, |c, v| c.randomize_min = v),
+        OscParam::resource_bool::<WipeAutomationControls>("wipe/auto/rand_max", "Random end size", |c| c.randomize_max, |c, v| c.randomize_max = v),
+        OscParam::resource::<WipeAutomationControls>("wipe/auto/time", "Duration of automatic wipes", 0. ..=1., |c| c.wipe_time, |c, v| c.wipe_time = v),
+        OscParam::resource_int::<WipeAutomationControls>("wipe/auto/beat_div", "Wipe every n beats", 0..=32, |c| c.beat_div as i32, |c, v| c.beat_div = v as usize),
+    ]).collect()
+}
+
+/// Keys 1-5 trigger a wipe of each shape, 6 one of a random shape
+fn wipe_key_actions() -> Vec<KeyAction> {
+    const KEYS: [&str; 5] = ["1", "2", "3", "4", "5"];
+    WipeShape::ALL.into_iter().zip(KEYS)
+        .map(|(shape, key)| KeyAction::trigger(
+            format!("wipe_{}", shape.name()), format!("Wipe {}", shape.name()), Some(key),
+            move |world| send_manual_wipe(world, shape.clone()),
+        ))
+        .chain([KeyAction::trigger("wipe_random", "Wipe random shape", Some("6"),
+            |world| send_manual_wipe(world, rand::random()),
+        )])
+        .collect()
+}
+
+fn send_manual_wipe(world: &mut World, shape: WipeShape) {
+    let Some(controls) = world.get_resource::<ManualWipeControls>() else { return; };
+    let event = WipeEvent { shape, ..controls.event.clone() };
+    world.resource_mut::<Events<WipeEvent>>().send(event);
+}
+
+/// `/vj/wipe/trigger [shape] [steps]`
+fn wipe_trigger(world: &mut World, args: &[OscType]) -> Result<(), String> {
+    let mut event = world.get_resource::<ManualWipeControls>().ok_or("wipes not available")?.event.clone();
+    match args.first() {
+        Some(OscType::String(name)) => {
+            event.shape = WipeShape::ALL.into_iter().find(|s| s.name() == name.as_str())
+                .ok_or_else(|| format!("unknown shape {}", name))?;
+        }
+        Some(arg) => {
+            let index = arg_int(arg).ok_or("shape must be a name or index")?;
+            event.shape = WipeShape::ALL.get(index.max(0) as usize).cloned()
+                .ok_or_else(|| format!("shape index {} out of range", index))?;
+        }
+        None => {}
+    }
+    if let Some(arg) = args.get(1) {
+        event.steps = arg_int(arg).ok_or("steps must be a number")?.clamp(1, 20) as isize;
+    }
+    world.resource_mut::<Events<WipeEvent>>().send(event);
+    Ok(())
+}
+
 pub fn spawn_rd(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<RDShaderMaterial>>,
     mut std_materials: ResMut<Assets<StandardMaterial>>,
     rd_rt: Res<RDRenderTarget>,
+    resolution: Res<RDResolution>,
+    simulation: Res<RDSimulation>,
+    shared_textures: Res<SharedTextures>,
+    kernel: Res<RDKernel>,
 ) {
-    let material_handle = materials.add(RDShaderMaterial {
+    let material = RDShaderMaterial {
         previous_rt: rd_rt.render_target.clone(),
         da: 1.0,
         db: 0.3,
         feed: 0.0287,
         kill: 0.078,
-    });
-
-    spawn_fs_quad::<RDShaderMaterial>(
-        &mut commands,
-        &mut meshes,
-        rd_rt.render_target.clone(),
-        material_handle,
-        4,
-        1000,
-    );
+        resolution: resolution.as_vec2(),
+        map_mode: 1,
+        // Matches the rings the shader used to have built in
+        map_strength: Vec4::new(0.015, 0.015, 0., 0.),
+        map: shared_textures.placeholder.clone(),
+        action: 0,
+        action_params: Vec4::ZERO,
+        model: RDModel::GrayScott.code(),
+        fhn: Vec4::from_array(RDModel::FitzHughNagumo.defaults()),
+        brusselator: Vec4::from_array(RDModel::Brusselator.defaults()),
+        cyclic: Vec4::from_array(RDModel::Cyclic.defaults()),
+        lenia: Vec4::from_array(RDModel::Lenia.defaults()),
+        laplace: kernel.uniform(),
+    };
+    spawn_rd_passes(&mut commands, &mut meshes, &mut materials, material, &rd_rt, simulation.max_substeps);
 
     commands
         .spawn(Camera2dBundle {
@@ -137,16 +483,13 @@ pub fn spawn_rd(
                 ..default()
             },
             projection: OrthographicProjection {
-                scaling_mode: ScalingMode::Fixed {
-                    width: 100.,
-                    height: 100.,
-                },
+                scaling_mode: wipe_projection(&resolution),
                 ..default()
             },
             transform: Transform::from_xyz(1920., 0., 100.),
             ..default()
         })
-        .insert(RenderLayers::layer(4));
+        .insert((RenderLayers::layer(4), WipeCamera));
 
     /*spawn_render_image_to_screen(
         &mut commands,
@@ -156,3 +499,26 @@ pub fn spawn_rd(
         RenderLayers::layer(31),
     );*/
 }
+
+/// Reallocates the buffers when the resolution changed
+pub fn rd_resize_system(
+    resolution: Res<RDResolution>,
+    rd_rt: Res<RDRenderTarget>,
+    mut images: ResMut<Assets<Image>>,
+    mut materials: ResMut<Assets<RDShaderMaterial>>,
+    mut wipe_cameras: Query<&mut OrthographicProjection, With<WipeCamera>>,
+) {
+    if !resolution.is_changed() || resolution.is_added() { return; }
+
+    for handle in [&rd_rt.render_target, &rd_rt.back_buffer] {
+        if let Some(image) = images.get_mut(handle) {
+            image.resize(resolution.extent());
+        }
+    }
+    for (_, material) in materials.iter_mut() {
+        material.resolution = resolution.as_vec2();
+    }
+    for mut projection in wipe_cameras.iter_mut() {
+        projection.scaling_mode = wipe_projection(&resolution);
+    }
+}
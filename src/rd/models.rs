@@ -0,0 +1,161 @@
+use std::ops::RangeInclusive;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::osc::{material, material_mut, MaterialCopy, OscParam, ParamKind};
+use crate::rd::actions::RDAction;
+use crate::rd::RDShaderMaterial;
+
+/// Simulation run by `rd.wgsl`. All of them keep their state in the red and green channels.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[repr(usize)]
+pub enum RDModel {
+    #[default]
+    GrayScott,
+    FitzHughNagumo,
+    Brusselator,
+    /// Cyclic cellular automaton, a cell is eaten by the next species around it
+    Cyclic,
+    /// Lenia, Game of Life at radius 1
+    Lenia,
+}
+
+/// One parameter of a model, shown in the UI and mapped to OSC
+pub struct ModelParam {
+    pub name: &'static str,
+    pub osc: &'static str,
+    pub description: &'static str,
+    pub range: RangeInclusive<f32>,
+    pub default: f32,
+    /// Drag speed in the UI
+    pub speed: f64,
+}
+
+const fn param(
+    name: &'static str, osc: &'static str, description: &'static str,
+    range: RangeInclusive<f32>, default: f32, speed: f64,
+) -> ModelParam {
+    ModelParam { name, osc, description, range, default, speed }
+}
+
+impl RDModel {
+    pub const ALL: [RDModel; 5] = [
+        RDModel::GrayScott, RDModel::FitzHughNagumo, RDModel::Brusselator, RDModel::Cyclic, RDModel::Lenia,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            RDModel::GrayScott => "Gray-Scott",
+            RDModel::FitzHughNagumo => "FitzHugh-Nagumo",
+            RDModel::Brusselator => "Brusselator",
+            RDModel::Cyclic => "Cyclic",
+            RDModel::Lenia => "Lenia / Life",
+        }
+    }
+
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+
+    /// Value of `uniform_model` in `rd.wgsl`
+    pub fn code(&self) -> u32 {
+        self.index() as u32
+    }
+
+    pub fn from_code(code: u32) -> Self {
+        Self::ALL.get(code as usize).copied().unwrap_or_default()
+    }
+
+    /// Parameters in the order of `values`
+    pub fn params(&self) -> [ModelParam; 4] {
+        match self {
+            RDModel::GrayScott => [
+                param("Da", "rd/da", "Diffusion rate of A", 0. ..=1., 1., 0.01),
+                param("Db", "rd/db", "Diffusion rate of B", 0. ..=1., 0.3, 0.01),
+                param("Feed", "rd/feed", "Feed rate", 0. ..=0.1, 0.0287, 0.001),
+                param("Kill", "rd/kill", "Kill rate", 0. ..=0.2, 0.078, 0.001),
+            ],
+            RDModel::FitzHughNagumo => [
+                param("Du", "rd/fhn/du", "Diffusion rate of the activator", 0. ..=4., 1., 0.01),
+                param("Dv", "rd/fhn/dv", "Diffusion rate of the inhibitor", 0. ..=10., 8., 0.05),
+                param("Epsilon", "rd/fhn/epsilon", "Speed of the inhibitor", 0. ..=1., 0.2, 0.005),
+                param("a0", "rd/fhn/a0", "Offset of the inhibitor, shifts between spots and stripes", -0.5..=0.5, 0., 0.005),
+            ],
+            RDModel::Brusselator => [
+                param("a", "rd/brusselator/a", "Supply of X", 0. ..=8., 4.5, 0.05),
+                param("b", "rd/brusselator/b", "Conversion of X to Y", 0. ..=16., 6.75, 0.05),
+                param("Dx", "rd/brusselator/dx", "Diffusion rate of X", 0. ..=10., 2., 0.05),
+                param("Dy", "rd/brusselator/dy", "Diffusion rate of Y", 0. ..=50., 16., 0.1),
+            ],
+            RDModel::Cyclic => [
+                param("Species", "rd/cyclic/species", "Number of species", 2. ..=16., 8., 0.1),
+                param("Threshold", "rd/cyclic/threshold", "Neighbours of the next species needed to take over", 1. ..=24., 3., 0.1),
+                param("Range", "rd/cyclic/range", "Neighbourhood radius in pixels", 1. ..=2., 1., 0.05),
+                param("Noise", "rd/cyclic/noise", "Chance of a cell advancing on its own", 0. ..=0.01, 0., 0.0001),
+            ],
+            RDModel::Lenia => [
+                param("Radius", "rd/lenia/radius", "Kernel radius in pixels, 1 runs the Game of Life", 1. ..=8., 6., 0.05),
+                param("Mu", "rd/lenia/mu", "Neighbourhood density that grows", 0. ..=0.5, 0.15, 0.001),
+                param("Sigma", "rd/lenia/sigma", "Width of the growth band", 0.001..=0.1, 0.017, 0.0005),
+                param("dt", "rd/lenia/dt", "Growth per step", 0.01..=1., 0.1, 0.005),
+            ],
+        }
+    }
+
+    pub fn defaults(&self) -> [f32; 4] {
+        self.params().map(|p| p.default)
+    }
+
+    /// Parameters of this model in `mat`
+    pub fn values(&self, mat: &RDShaderMaterial) -> [f32; 4] {
+        match self {
+            RDModel::GrayScott => [mat.da, mat.db, mat.feed, mat.kill],
+            RDModel::FitzHughNagumo => mat.fhn.to_array(),
+            RDModel::Brusselator => mat.brusselator.to_array(),
+            RDModel::Cyclic => mat.cyclic.to_array(),
+            RDModel::Lenia => mat.lenia.to_array(),
+        }
+    }
+
+    pub fn values_mut<'a>(&self, mat: &'a mut RDShaderMaterial) -> [&'a mut f32; 4] {
+        let params: &mut [f32; 4] = match self {
+            RDModel::GrayScott => return [&mut mat.da, &mut mat.db, &mut mat.feed, &mut mat.kill],
+            RDModel::FitzHughNagumo => mat.fhn.as_mut(),
+            RDModel::Brusselator => mat.brusselator.as_mut(),
+            RDModel::Cyclic => mat.cyclic.as_mut(),
+            RDModel::Lenia => mat.lenia.as_mut(),
+        };
+        let [a, b, c, d] = params;
+        [a, b, c, d]
+    }
+}
+
+/// `/vj/rd/model` and the parameters of every model
+pub fn model_osc_params() -> Vec<OscParam> {
+    let model = OscParam::new(
+        "rd/model", "Simulation model, 0 Gray-Scott, 1 FitzHugh-Nagumo, 2 Brusselator, 3 cyclic, 4 Lenia",
+        ParamKind::Int, 0. ..=(RDModel::ALL.len() - 1) as f32,
+        |world| material::<RDShaderMaterial>(world).map(|m| m.model as f32),
+        |world, v| if let Some(mat) = material_mut::<RDShaderMaterial>(world) { mat.model = RDModel::from_code(v as u32).code() },
+    );
+    let params = RDModel::ALL.into_iter().flat_map(|model| {
+        model.params().into_iter().enumerate().map(move |(i, p)| OscParam::material::<RDShaderMaterial>(
+            p.osc, p.description, p.range,
+            move |m| model.values(m)[i],
+            move |m, v| *model.values_mut(m)[i] = v,
+        ))
+    });
+    std::iter::once(model).chain(params).collect()
+}
+
+/// The state of one model means nothing to another, so switching reseeds the field
+pub fn rd_model_system(
+    mat_query: Query<&Handle<RDShaderMaterial>, Without<MaterialCopy>>,
+    materials: Res<Assets<RDShaderMaterial>>,
+    mut action_writer: EventWriter<RDAction>,
+    mut last_model: Local<Option<u32>>,
+) {
+    let Some(mat) = mat_query.get_single().ok().and_then(|h| materials.get(h)) else { return; };
+    if last_model.replace(mat.model).map_or(false, |last| last != mat.model) {
+        action_writer.send(RDAction::Noise);
+    }
+}
@@ -0,0 +1,141 @@
+use bevy::prelude::*;
+use bevy::render::view::RenderLayers;
+use bevy_egui::{egui, EguiContexts};
+use bevy_smud::prelude::*;
+use crate::rd::{RDRenderTarget, RDResolution};
+
+const PREVIEW_WIDTH: f32 = 480.;
+
+/// What the brush puts into the field
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BrushChemical {
+    B,
+    /// Back to the base state, all A and no B, like `RDAction::Clear`
+    Erase,
+}
+
+impl BrushChemical {
+    pub const ALL: [BrushChemical; 2] = [BrushChemical::B, BrushChemical::Erase];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            BrushChemical::B => "B",
+            BrushChemical::Erase => "Erase",
+        }
+    }
+
+    fn color(&self, strength: f32) -> Color {
+        match self {
+            BrushChemical::B => Color::rgba(0., 1., 0., strength),
+            BrushChemical::Erase => Color::rgba(1., 0., 0., strength),
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct BrushControls {
+    /// Radius in the 100 units high wipe space
+    pub size: f32,
+    /// Opacity of every dab, low values build up over a stroke
+    pub strength: f32,
+    pub chemical: BrushChemical,
+    /// Dabs to draw this frame, in wipe space
+    stroke: Vec<Vec2>,
+    last: Option<Vec2>,
+}
+
+impl Default for BrushControls {
+    fn default() -> Self {
+        Self { size: 3., strength: 1., chemical: BrushChemical::B, stroke: vec![], last: None }
+    }
+}
+
+impl BrushControls {
+    /// Adds dabs from the last position to `pos`, close enough to form a line
+    fn drag_to(&mut self, pos: Vec2) {
+        let from = self.last.unwrap_or(pos);
+        let spacing = (self.size * 0.25).max(0.1);
+        let count = (from.distance(pos) / spacing).ceil().max(1.) as usize;
+        self.stroke.extend((1..=count).map(|i| from.lerp(pos, i as f32 / count as f32)));
+        self.last = Some(pos);
+    }
+}
+
+/// Drawn by the wipe camera for a single frame
+#[derive(Component)]
+pub struct BrushDab;
+
+struct BrushShader(Handle<Shader>);
+
+impl FromWorld for BrushShader {
+    fn from_world(world: &mut World) -> Self {
+        let mut shaders = world.resource_mut::<Assets<Shader>>();
+        Self(shaders.add_sdf_expr("sd_circle(p, params.x)"))
+    }
+}
+
+/// Preview of the field to paint on
+pub fn paint_ui(
+    mut contexts: EguiContexts,
+    mut brush: ResMut<BrushControls>,
+    rd_rt: Res<RDRenderTarget>,
+    resolution: Res<RDResolution>,
+) {
+    let texture = contexts.add_image(rd_rt.render_target.clone_weak());
+    let brush = &mut *brush;
+
+    egui::Window::new("RD Paint").default_open(false).show(contexts.ctx_mut(), |ui| {
+        ui.horizontal(|ui| {
+            for chemical in BrushChemical::ALL {
+                ui.selectable_value(&mut brush.chemical, chemical, chemical.name());
+            }
+        });
+        ui.add(egui::Slider::new(&mut brush.size, 0.5..=20.0).text("Size"));
+        ui.add(egui::Slider::new(&mut brush.strength, 0.0..=1.0).text("Strength"));
+
+        let size = egui::vec2(PREVIEW_WIDTH, PREVIEW_WIDTH / resolution.aspect());
+        let response = ui.add(egui::Image::new(texture, size).sense(egui::Sense::drag()));
+        match response.interact_pointer_pos().filter(|_| response.dragged()) {
+            Some(pos) => {
+                let rect = response.rect;
+                let uv = egui::vec2((pos.x - rect.left()) / rect.width(), (pos.y - rect.top()) / rect.height());
+                let pos = Vec2::new((uv.x - 0.5) * 100. * resolution.aspect(), (0.5 - uv.y) * 100.);
+                brush.drag_to(pos);
+            }
+            None => brush.last = None,
+        }
+    });
+}
+
+/// Replaces last frame's dabs with this frame's stroke
+pub fn paint_system(
+    mut commands: Commands,
+    mut brush: ResMut<BrushControls>,
+    dabs: Query<Entity, With<BrushDab>>,
+    shader: Local<BrushShader>,
+) {
+    for entity in dabs.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    let color = brush.chemical.color(brush.strength);
+    let size = brush.size;
+    for pos in brush.stroke.drain(..) {
+        commands.spawn((
+            ShapeBundle {
+                shape: SmudShape {
+                    color,
+                    sdf: shader.0.clone(),
+                    frame: Frame::Quad(size + 1.),
+                    params: Vec4::new(size, 0., 0., 0.),
+                    ..default()
+                },
+                // Above the seeds
+                transform: Transform::from_xyz(1920. + pos.x, pos.y, 3.),
+                ..default()
+            },
+            RenderLayers::layer(4),
+            BrushDab,
+        ));
+    }
+}
@@ -0,0 +1,270 @@
+use std::path::Path;
+use bevy::prelude::*;
+use bevy::render::view::RenderLayers;
+use bevy::sprite::Anchor;
+use bevy_egui::{egui, EguiContexts};
+use rand::random;
+use serde::{Deserialize, Serialize};
+use crate::autosave::ModuleState;
+use crate::rd::RDResolution;
+use crate::shared_textures::SharedTextures;
+use crate::status::{load_font, StatusPanel};
+
+/// Height of a seed at scale 1, in the 100 units high wipe space
+const SEED_HEIGHT: f32 = 20.;
+const TEXT_FONT_SIZE: f32 = 100.;
+
+/// What gets stamped into the field as B chemical
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[repr(usize)]
+pub enum SeedSource {
+    Image,
+    Text,
+    ChipSpin,
+}
+
+impl SeedSource {
+    pub const ALL: [SeedSource; 3] = [SeedSource::Image, SeedSource::Text, SeedSource::ChipSpin];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SeedSource::Image => "Image",
+            SeedSource::Text => "Text",
+            SeedSource::ChipSpin => "Chip Spin",
+        }
+    }
+
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+#[derive(Resource, Clone, Serialize, Deserialize)]
+pub struct SeedControls {
+    pub source: SeedSource,
+    /// Relative to the assets folder
+    pub image_path: String,
+    pub text: String,
+    /// Center in the 100 units high wipe space, 0 is the middle of the field
+    pub position: [f32; 2],
+    pub random_position: bool,
+    pub scale: f32,
+    /// How long the seed is drawn into the field
+    pub duration: f32,
+    pub on_beat: bool,
+    pub beat_div: usize,
+    #[serde(skip)]
+    pub beat_count: usize,
+}
+
+impl FromWorld for SeedControls {
+    fn from_world(_world: &mut World) -> Self {
+        Self {
+            source: SeedSource::Image,
+            image_path: "images/plakat_scaled_clean.png".to_owned(),
+            text: "GPN21".to_owned(),
+            position: [0., 0.],
+            random_position: false,
+            scale: 1.,
+            duration: 0.1,
+            on_beat: false,
+            beat_div: 4,
+            beat_count: 0,
+        }
+    }
+}
+
+impl SeedControls {
+    pub fn event(&self, resolution: &RDResolution) -> SeedEvent {
+        // Random seeds keep away from the edges
+        let position = match self.random_position {
+            true => Vec2::new(random::<f32>() - 0.5, random::<f32>() - 0.5) * Vec2::new(resolution.wipe_width(), 100.) * 0.8,
+            false => Vec2::from(self.position),
+        };
+        SeedEvent { source: self.source, position, scale: self.scale, duration: self.duration }
+    }
+}
+
+impl ModuleState for SeedControls {
+    const KEY: &'static str = "rd_seeds";
+
+    fn capture(world: &mut World) -> Option<Self> {
+        world.get_resource::<Self>().cloned()
+    }
+
+    fn apply(&self, world: &mut World) {
+        world.insert_resource(self.clone());
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SeedEvent {
+    pub source: SeedSource,
+    pub position: Vec2,
+    pub scale: f32,
+    pub duration: f32,
+}
+
+#[derive(Component)]
+pub struct SeedElement {
+    age: f32,
+    duration: f32,
+}
+
+#[derive(Resource)]
+pub struct SeedAssets {
+    font: Handle<Font>,
+    image: Handle<Image>,
+    /// Path `image` was loaded from
+    image_path: String,
+}
+
+pub fn setup_seeds(
+    mut commands: Commands,
+    assets: Res<AssetServer>,
+    mut fonts: ResMut<Assets<Font>>,
+    mut status: ResMut<StatusPanel>,
+) {
+    let font = load_font("fonts/Roboto-Bold.ttf", &assets, &mut fonts, &mut status);
+    commands.insert_resource(SeedAssets { font, image: Handle::default(), image_path: String::new() });
+}
+
+/// Stamps seeds into the field on layer 4, where the wipe camera draws them on top of the simulation
+pub fn seed_event_listener_system(
+    mut event_reader: EventReader<SeedEvent>,
+    mut commands: Commands,
+    controls: Res<SeedControls>,
+    mut seed_assets: ResMut<SeedAssets>,
+    asset_server: Res<AssetServer>,
+    images: Res<Assets<Image>>,
+    shared_textures: Res<SharedTextures>,
+    mut status: ResMut<StatusPanel>,
+) {
+    for event in event_reader.iter() {
+        let translation = Vec3::new(1920. + event.position.x, event.position.y, 2.);
+        let seed = SeedElement { age: 0., duration: event.duration };
+
+        let texture = match event.source {
+            SeedSource::Text => {
+                commands.spawn((
+                    Text2dBundle {
+                        text: Text::from_section(controls.text.clone(), TextStyle {
+                            font: seed_assets.font.clone(),
+                            font_size: TEXT_FONT_SIZE,
+                            color: Color::GREEN,
+                        }).with_alignment(TextAlignment::Center),
+                        text_anchor: Anchor::Center,
+                        transform: Transform::from_translation(translation)
+                            .with_scale(Vec3::splat(event.scale * SEED_HEIGHT / TEXT_FONT_SIZE)),
+                        ..default()
+                    },
+                    RenderLayers::layer(4),
+                    seed,
+                ));
+                continue;
+            }
+            SeedSource::ChipSpin => shared_textures.chip_spin.clone(),
+            SeedSource::Image => {
+                if seed_assets.image_path != controls.image_path {
+                    seed_assets.image_path = controls.image_path.clone();
+                    seed_assets.image = match asset_server.asset_io().is_file(Path::new(&controls.image_path)) {
+                        true => asset_server.load(controls.image_path.as_str()),
+                        false => Handle::default(),
+                    };
+                }
+                if seed_assets.image == Handle::default() {
+                    status.warn("rd_seed", format!("RD seed image {} not found", controls.image_path));
+                    continue;
+                }
+                status.resolve("rd_seed");
+                seed_assets.image.clone()
+            }
+        };
+
+        // Square until the image is loaded
+        let aspect = images.get(&texture).map_or(1., |i| i.size().x / i.size().y);
+        let height = SEED_HEIGHT * event.scale;
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: Color::GREEN,
+                    custom_size: Some(Vec2::new(height * aspect, height)),
+                    ..default()
+                },
+                texture,
+                transform: Transform::from_translation(translation),
+                ..default()
+            },
+            RenderLayers::layer(4),
+            seed,
+        ));
+    }
+}
+
+pub fn seed_system(
+    mut query: Query<(Entity, &mut SeedElement)>,
+    mut commands: Commands,
+    time: Res<Time>,
+) {
+    for (entity, mut seed) in query.iter_mut() {
+        seed.age += time.delta_seconds();
+        if seed.age > seed.duration {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+pub fn seed_ui(
+    mut contexts: EguiContexts,
+    mut controls: ResMut<SeedControls>,
+    mut event_writer: EventWriter<SeedEvent>,
+    resolution: Res<RDResolution>,
+) {
+    egui::Window::new("RD Seeds").default_open(false).show(contexts.ctx_mut(), |ui| {
+        egui::ComboBox::from_label("Source")
+            .selected_text(controls.source.name())
+            .show_ui(ui, |ui| {
+                for s in SeedSource::ALL {
+                    ui.selectable_value(&mut controls.source, s, s.name());
+                }
+            });
+        match controls.source {
+            SeedSource::Image => { ui.text_edit_singleline(&mut controls.image_path); }
+            SeedSource::Text => { ui.text_edit_singleline(&mut controls.text); }
+            SeedSource::ChipSpin => {}
+        }
+
+        egui::Grid::new("seed params")
+            .num_columns(2)
+            .spacing([40.0, 4.0])
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label("Position");
+                ui.horizontal(|ui| {
+                    ui.add(egui::DragValue::new(&mut controls.position[0]).speed(0.5).clamp_range(resolution.wipe_x_range()));
+                    ui.add(egui::DragValue::new(&mut controls.position[1]).speed(0.5).clamp_range(-50.0..=50.0));
+                    ui.checkbox(&mut controls.random_position, "Random");
+                });
+                ui.end_row();
+
+                ui.label("Scale");
+                ui.add(egui::Slider::new(&mut controls.scale, 0.1..=5.0));
+                ui.end_row();
+
+                ui.label("Duration");
+                ui.add(egui::Slider::new(&mut controls.duration, 0.0..=1.0));
+                ui.end_row();
+            });
+
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut controls.on_beat, "On Beat");
+            ui.label("every");
+            ui.add(egui::DragValue::new(&mut controls.beat_div).clamp_range(1..=16));
+            ui.label("beats");
+        });
+
+        if ui.button("Seed").clicked() {
+            event_writer.send(controls.event(&resolution));
+        }
+    });
+}
@@ -0,0 +1,193 @@
+use bevy::core_pipeline::clear_color::ClearColorConfig;
+use bevy::core_pipeline::tonemapping::{DebandDither, Tonemapping};
+use bevy::prelude::*;
+use bevy::render::camera::{RenderTarget, ScalingMode};
+use bevy::render::view::RenderLayers;
+use crate::config::AppConfig;
+use crate::osc::MaterialCopy;
+use crate::rd::{RDRenderTarget, RDShaderMaterial, WipeCamera};
+use crate::rd::actions::RDActions;
+use crate::shared_textures::SharedTextures;
+
+/// Layer of the quad reading the front buffer, the one reading the back buffer is on the next
+const PASS_LAYER: u8 = 20;
+/// Order of the last pass, the wipes are drawn on top right after
+const LAST_PASS_ORDER: isize = 1000;
+
+/// Advances the simulation by a fixed number of steps per second, independent of the frame rate
+#[derive(Resource)]
+pub struct RDSimulation {
+    pub speed: f32,
+    pub steps_per_second: f32,
+    /// Number of passes spawned, the most steps run in one frame
+    pub max_substeps: usize,
+    /// Steps run this frame
+    pub steps: usize,
+    /// The frame needed more steps than `max_substeps`, the simulation runs slower than set
+    pub saturated: bool,
+    accumulator: f32,
+}
+
+impl FromWorld for RDSimulation {
+    fn from_world(world: &mut World) -> Self {
+        let config = world.get_resource::<AppConfig>().map(|c| c.rd.clone()).unwrap_or_default();
+        Self {
+            speed: 1.,
+            steps_per_second: config.steps_per_second,
+            max_substeps: config.max_substeps,
+            steps: 0,
+            saturated: false,
+            accumulator: 0.,
+        }
+    }
+}
+
+/// Camera of one simulation step
+#[derive(Component)]
+pub struct RDPass(pub usize);
+
+/// Spawns the two quads and a camera per substep.
+/// Even passes render the front buffer into the back buffer, odd ones the other way around.
+pub fn spawn_rd_passes(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<RDShaderMaterial>,
+    material: RDShaderMaterial,
+    rd_rt: &RDRenderTarget,
+    max_substeps: usize,
+) {
+    let quad = meshes.add(Mesh::from(shape::Quad::new(Vec2::splat(2.))));
+    let back_material = RDShaderMaterial { previous_rt: rd_rt.back_buffer.clone(), ..material.clone() };
+
+    commands.spawn((
+        MaterialMeshBundle { mesh: quad.clone(), material: materials.add(material), ..default() },
+        RenderLayers::layer(PASS_LAYER),
+    ));
+    commands.spawn((
+        MaterialMeshBundle { mesh: quad, material: materials.add(back_material), ..default() },
+        RenderLayers::layer(PASS_LAYER + 1),
+        MaterialCopy,
+    ));
+
+    for pass in 0..max_substeps {
+        commands.spawn((
+            Camera3dBundle {
+                camera: Camera {
+                    order: LAST_PASS_ORDER - (max_substeps - 1 - pass) as isize,
+                    target: RenderTarget::Image(pass_target(rd_rt, pass).clone()),
+                    is_active: false,
+                    ..default()
+                },
+                camera_3d: Camera3d {
+                    clear_color: ClearColorConfig::None,
+                    ..default()
+                },
+                projection: Projection::Orthographic(OrthographicProjection {
+                    scaling_mode: ScalingMode::Fixed { width: 2., height: 2. },
+                    ..default()
+                }),
+                // The simulation state lives in the colors, they must not be touched
+                tonemapping: Tonemapping::None,
+                dither: DebandDither::Disabled,
+                transform: Transform::from_xyz(0., 0., 1.).looking_at(Vec3::ZERO, Vec3::Y),
+                ..default()
+            },
+            RenderLayers::layer(PASS_LAYER + (pass % 2) as u8),
+            RDPass(pass),
+        ));
+    }
+}
+
+fn pass_target(rd_rt: &RDRenderTarget, pass: usize) -> &Handle<Image> {
+    match pass % 2 {
+        0 => &rd_rt.back_buffer,
+        _ => &rd_rt.render_target,
+    }
+}
+
+/// Only touches the camera when the target is different, a changed camera recomputes its target info
+fn retarget(camera: &mut Mut<Camera>, image: &Handle<Image>) {
+    if !matches!(&camera.target, RenderTarget::Image(current) if current == image) {
+        camera.target = RenderTarget::Image(image.clone());
+    }
+}
+
+/// Decides how many steps to run this frame and activates that many passes.
+/// An odd number of steps ends in the back buffer, so the buffers swap roles before the next frame.
+pub fn rd_step_system(
+    mut simulation: ResMut<RDSimulation>,
+    mut actions: ResMut<RDActions>,
+    mut rd_rt: ResMut<RDRenderTarget>,
+    mut shared_textures: ResMut<SharedTextures>,
+    mut passes: Query<(&mut Camera, &RDPass)>,
+    mut wipe_cameras: Query<&mut Camera, (With<WipeCamera>, Without<RDPass>)>,
+    mat_query: Query<&Handle<RDShaderMaterial>, Without<MaterialCopy>>,
+    mut materials: ResMut<Assets<RDShaderMaterial>>,
+    time: Res<Time>,
+) {
+    let simulation = &mut *simulation;
+    if simulation.steps % 2 == 1 {
+        let rd_rt = &mut *rd_rt;
+        std::mem::swap(&mut rd_rt.render_target, &mut rd_rt.back_buffer);
+        // The back material follows in rd_sync_back_material
+        if let Some(mat) = mat_query.get_single().ok().and_then(|h| materials.get_mut(h)) {
+            mat.previous_rt = rd_rt.render_target.clone();
+        }
+        for (mut camera, RDPass(pass)) in passes.iter_mut() {
+            retarget(&mut camera, pass_target(rd_rt, *pass));
+        }
+    }
+
+    simulation.accumulator += time.delta_seconds() * simulation.speed * simulation.steps_per_second;
+    let due = simulation.accumulator.floor() as usize;
+    simulation.steps = due.min(simulation.max_substeps);
+    simulation.accumulator -= simulation.steps as f32;
+    simulation.saturated = due > simulation.max_substeps;
+    if simulation.saturated {
+        // Don't build up a backlog that would speed the simulation up later
+        simulation.accumulator = simulation.accumulator.min(1.);
+    }
+    if actions.pending.take().is_some() {
+        // Only the action pass runs, the third pass would run it again with the main material. Runs even when paused.
+        simulation.accumulator += simulation.steps as f32;
+        simulation.steps = 1;
+    }
+
+    for (mut camera, RDPass(pass)) in passes.iter_mut() {
+        let active = *pass < simulation.steps;
+        if camera.is_active != active {
+            camera.is_active = active;
+        }
+    }
+
+    // Wipes and seeds are drawn into the buffer the frame ends in, and the other modules read that one
+    let result = match simulation.steps % 2 {
+        0 => &rd_rt.render_target,
+        _ => &rd_rt.back_buffer,
+    };
+    for mut camera in wipe_cameras.iter_mut() {
+        retarget(&mut camera, result);
+    }
+    if shared_textures.rd != *result {
+        shared_textures.rd = result.clone();
+    }
+}
+
+/// Copies the parameters of the main material, which the UI, OSC and presets change, to the back pass.
+/// Runs in `PostUpdate`, after everything that writes to the main material.
+pub fn rd_sync_back_material(
+    main: Query<&Handle<RDShaderMaterial>, Without<MaterialCopy>>,
+    back: Query<&Handle<RDShaderMaterial>, With<MaterialCopy>>,
+    mut materials: ResMut<Assets<RDShaderMaterial>>,
+    rd_rt: Res<RDRenderTarget>,
+) {
+    // Any mutable access to a material marks the assets as changed
+    if !materials.is_changed() && !rd_rt.is_changed() { return; }
+    let (Ok(main), Ok(back)) = (main.get_single(), back.get_single()) else { return; };
+    let Some(material) = materials.get(main).cloned() else { return; };
+    // Not a change by itself, otherwise every frame would copy again
+    if let Some(back) = materials.bypass_change_detection().get_mut(back) {
+        // Actions only run in the first pass
+        *back = RDShaderMaterial { previous_rt: rd_rt.back_buffer.clone(), action: 0, ..material };
+    }
+}
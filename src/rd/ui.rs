@@ -6,9 +6,20 @@ use crate::rd::RDShaderMaterial;
 use bevy_egui::{egui, EguiContexts};
 use bevy_egui::egui::WidgetType::{ComboBox, DragValue};
 use rand::random;
+use serde::{Deserialize, Serialize};
 use crate::rd::wipes::WipeShape::Circle;
+use crate::autosave::ModuleState;
+use crate::presets::{PresetBank, PresetEvent, PresetModule};
+use crate::osc::MaterialCopy;
+use crate::rd::{RDPreset, RDResolution};
+use crate::rd::simulation::RDSimulation;
+use crate::rd::models::RDModel;
+use crate::rd::actions::{RDAction, RDActionAutomation, RDActions};
+use crate::config::RD_SIZE_LIMITS;
+use crate::midi::{midi_learn_button, MidiMappings};
+use crate::status::StatusPanel;
 
-#[derive(Resource)]
+#[derive(Resource, Clone, Serialize, Deserialize)]
 pub struct WipeAutomationControls {
     pub on_beat: bool,
     pub randomize_shape: bool,
@@ -21,9 +32,38 @@ pub struct WipeAutomationControls {
     pub max_range: Range<f32>,
     pub wipe_time: f32,
     pub beat_div: usize,
+    #[serde(skip)]
     pub beat_count: usize,
 }
 
+/// Parameters used by the manual wipe buttons
+#[derive(Resource, Default, Clone, Serialize, Deserialize)]
+pub struct ManualWipeControls {
+    pub event: WipeEvent,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct WipePreset {
+    pub automation: WipeAutomationControls,
+    pub manual: ManualWipeControls,
+}
+
+impl ModuleState for WipePreset {
+    const KEY: &'static str = "wipes";
+
+    fn capture(world: &mut World) -> Option<Self> {
+        Some(Self {
+            automation: world.get_resource::<WipeAutomationControls>()?.clone(),
+            manual: world.get_resource::<ManualWipeControls>()?.clone(),
+        })
+    }
+
+    fn apply(&self, world: &mut World) {
+        world.insert_resource(self.automation.clone());
+        world.insert_resource(self.manual.clone());
+    }
+}
+
 impl FromWorld for WipeAutomationControls {
     fn from_world(world: &mut World) -> Self {
         Self {
@@ -45,70 +85,132 @@ impl FromWorld for WipeAutomationControls {
 
 pub fn ui_system(
     mut contexts: EguiContexts,
-    mut mat_query: Query<&Handle<RDShaderMaterial>>,
+    mut mat_query: Query<&Handle<RDShaderMaterial>, Without<MaterialCopy>>,
     mut materials: ResMut<Assets<RDShaderMaterial>>,
     mut event_writer: EventWriter<WipeEvent>,
-    mut local_event: Local<WipeEvent>,
+    mut manual_controls: ResMut<ManualWipeControls>,
     mut automation_controls: ResMut<WipeAutomationControls>,
-    keys: Res<Input<KeyCode>>,
+    rd_presets: Res<PresetBank<RDPreset>>,
+    mut preset_writer: EventWriter<PresetEvent>,
+    mut status: ResMut<StatusPanel>,
+    mut midi: ResMut<MidiMappings>,
+    mut resolution: ResMut<RDResolution>,
+    mut pending_resolution: Local<Option<RDResolution>>,
+    mut simulation: ResMut<RDSimulation>,
+    mut action_writer: EventWriter<RDAction>,
+    mut actions: ResMut<RDActions>,
+    mut action_automation: ResMut<RDActionAutomation>,
 ) {
-    let mat_handle = mat_query.get_single_mut().unwrap();
-    let mut mat = materials.get_mut(mat_handle).unwrap();
+    let local_event = &mut manual_controls.event;
+
+    let Some(mat) = mat_query.get_single_mut().ok().and_then(|h| materials.get_mut(h)) else {
+        status.error("rd_material", "RD material not found");
+        return;
+    };
+    status.resolve("rd_material");
 
     egui::Window::new("RD Shader").show(contexts.ctx_mut(), |ui| {
+        let mut model = RDModel::from_code(mat.model);
+        egui::ComboBox::from_label("Model")
+            .selected_text(model.name())
+            .show_ui(ui, |ui| {
+                for m in RDModel::ALL {
+                    ui.selectable_value(&mut model, m, m.name());
+                }
+            });
+        mat.model = model.code();
+
         egui::Grid::new("rd params")
-            .num_columns(2)
+            .num_columns(3)
             .spacing([40.0, 4.0])
             .striped(true)
             .show(ui, |ui| {
-                ui.label("Da");
-                ui.add(
-                    egui::DragValue::new(&mut mat.da)
-                        .speed(0.01)
-                        .max_decimals(2),
-                );
-                ui.end_row();
+                for (param, value) in model.params().into_iter().zip(model.values_mut(mat)) {
+                    ui.label(param.name).on_hover_text(param.description);
+                    ui.add(
+                        egui::DragValue::new(value)
+                            .speed(param.speed)
+                            .clamp_range(param.range.clone())
+                            .max_decimals(4),
+                    );
+                    midi_learn_button(ui, &mut midi, param.osc);
+                    ui.end_row();
+                }
+            });
 
-                ui.label("Db");
-                ui.add(
-                    egui::DragValue::new(&mut mat.db)
-                        .speed(0.01)
-                        .max_decimals(2),
-                );
-                ui.end_row();
+        ui.separator();
 
-                ui.label("Feed");
-                ui.add(
-                    egui::DragValue::new(&mut mat.feed)
-                        .speed(0.001)
-                        .max_decimals(4),
-                );
-                ui.end_row();
+        ui.horizontal(|ui| {
+            ui.label("Speed");
+            ui.add(egui::Slider::new(&mut simulation.speed, 0.0..=4.0));
+            midi_learn_button(ui, &mut midi, "rd/speed");
+        });
+        ui.label(format!("{} steps this frame", simulation.steps));
+        if simulation.saturated {
+            ui.colored_label(egui::Color32::YELLOW, format!("Limited to {} steps per frame, running slower", simulation.max_substeps));
+        }
 
-                ui.label("Kill");
-                ui.add(
-                    egui::DragValue::new(&mut mat.kill)
-                        .speed(0.001)
-                        .max_decimals(4),
-                );
-                ui.end_row();
-            });
+        ui.separator();
+
+        ui.horizontal(|ui| {
+            for action in RDAction::ALL {
+                if ui.button(action.name()).clicked() {
+                    action_writer.send(action);
+                }
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("Noise density");
+            ui.add(egui::Slider::new(&mut actions.noise_density, 0.0..=1.0));
+            midi_learn_button(ui, &mut midi, "rd/noise_density");
+        });
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_source("rd action automation")
+                .selected_text(action_automation.action.map_or("Off", |a| a.name()))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut action_automation.action, None, "Off");
+                    for action in RDAction::ALL {
+                        ui.selectable_value(&mut action_automation.action, Some(action), action.name());
+                    }
+                });
+            ui.label("every");
+            ui.add(egui::DragValue::new(&mut action_automation.beat_div).clamp_range(1..=64));
+            ui.label("beats");
+        });
+
+        ui.separator();
+
+        // Edited separately, every change reallocates and clears the simulation
+        let pending = pending_resolution.get_or_insert(*resolution);
+        ui.horizontal(|ui| {
+            ui.label("Resolution");
+            ui.add(egui::DragValue::new(&mut pending.width).clamp_range(RD_SIZE_LIMITS));
+            ui.label("x");
+            ui.add(egui::DragValue::new(&mut pending.height).clamp_range(RD_SIZE_LIMITS));
+        });
+        ui.horizontal(|ui| {
+            if ui.button("1024 x 1024").clicked() {
+                *pending = RDResolution { width: 1024, height: 1024 };
+            }
+            if ui.button("1920 x 610").clicked() {
+                *pending = RDResolution { width: 1920, height: 610 };
+            }
+            if ui.add_enabled(*pending != *resolution, egui::Button::new("Apply")).clicked() {
+                *resolution = *pending;
+            }
+        });
 
         ui.separator();
 
         ui.label("Presets");
-        if ui.button("Rings").clicked() {
-            mat.da = 1.;
-            mat.db = 0.3;
-            mat.feed = 0.0287;
-            mat.kill = 0.078;
-        }
-        if ui.button("Fuzzy").clicked() {
-            mat.da = 1.;
-            mat.db = 0.07;
-            mat.feed = 0.037;
-            mat.kill = 0.130;
-        }
+        ui.horizontal_wrapped(|ui| {
+            for (slot, preset) in rd_presets.slots.iter().enumerate() {
+                let Some(preset) = preset else { continue; };
+                if ui.button(&preset.name).clicked() {
+                    preset_writer.send(PresetEvent::recall(PresetModule::RD, slot));
+                }
+            }
+        });
 
         ui.separator();
 
@@ -145,38 +247,38 @@ pub fn ui_system(
             });
 
         ui.horizontal(|ui| {
-            if ui.button("Circle").clicked() || keys.just_pressed(KeyCode::Key1) {
+            if ui.button("Circle").clicked() {
                 event_writer.send(WipeEvent {
                     shape: WipeShape::Circle,
                     ..*local_event
                 })
             }
-            if ui.button("Octagon").clicked() || keys.just_pressed(KeyCode::Key2) {
+            if ui.button("Octagon").clicked() {
                 event_writer.send(WipeEvent {
                     shape: WipeShape::Octagon,
                     ..*local_event
                 })
             }
-            if ui.button("Cross").clicked() || keys.just_pressed(KeyCode::Key3) {
+            if ui.button("Cross").clicked() {
                 event_writer.send(WipeEvent {
                     shape: WipeShape::Cross,
                     ..*local_event
                 })
             }
-            if ui.button("Square").clicked() || keys.just_pressed(KeyCode::Key4) {
+            if ui.button("Square").clicked() {
                 event_writer.send(WipeEvent {
                     shape: WipeShape::Square,
                     ..*local_event
                 })
             }
-            if ui.button("Hexagram").clicked() || keys.just_pressed(KeyCode::Key5) {
+            if ui.button("Hexagram").clicked() {
                 event_writer.send(WipeEvent {
                     shape: WipeShape::Hexagram,
                     ..*local_event
                 })
             }
         });
-        if ui.button("Random Shape").clicked() || keys.just_pressed(KeyCode::Key6) {
+        if ui.button("Random Shape").clicked() {
             event_writer.send(WipeEvent {
                 shape: random(),
                 ..*local_event
@@ -6,8 +6,9 @@ use bevy_smud::prelude::*;
 use rand::distributions::Standard;
 use rand::prelude::Distribution;
 use rand::Rng;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum WipeShape {
     Circle,
     Octagon,
@@ -16,6 +17,24 @@ pub enum WipeShape {
     Hexagram,
 }
 
+impl WipeShape {
+    pub const ALL: [WipeShape; 5] = [Circle, Octagon, Cross, Square, Hexagram];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Circle => "circle",
+            Octagon => "octagon",
+            Cross => "cross",
+            Square => "square",
+            Hexagram => "hexagram",
+        }
+    }
+
+    pub fn index(&self) -> usize {
+        Self::ALL.iter().position(|s| s == self).unwrap_or(0)
+    }
+}
+
 impl Distribution<WipeShape> for Standard {
     fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> WipeShape {
         match rng.gen_range(0..=5) {
@@ -28,7 +47,7 @@ impl Distribution<WipeShape> for Standard {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct WipeEvent {
     pub(crate) shape: WipeShape,
     pub(crate) duration: f32,
@@ -0,0 +1,35 @@
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+
+/// Textures modules read from each other. Every slot starts out as a black placeholder
+/// and is replaced by the module that renders it, so a module can be left out of the
+/// build without the modules reading its output panicking.
+#[derive(Resource)]
+pub struct SharedTextures {
+    pub placeholder: Handle<Image>,
+    pub chip_spin: Handle<Image>,
+    pub fractal: Handle<Image>,
+    pub rd: Handle<Image>,
+    pub feedback: Handle<Image>,
+}
+
+impl FromWorld for SharedTextures {
+    fn from_world(world: &mut World) -> Self {
+        let mut images = world.get_resource_mut::<Assets<Image>>().unwrap();
+
+        let placeholder = images.add(Image::new_fill(
+            Extent3d { width: 1, height: 1, ..default() },
+            TextureDimension::D2,
+            &[0, 0, 0, 255],
+            TextureFormat::Rgba8UnormSrgb,
+        ));
+
+        Self {
+            chip_spin: placeholder.clone(),
+            fractal: placeholder.clone(),
+            rd: placeholder.clone(),
+            feedback: placeholder.clone(),
+            placeholder,
+        }
+    }
+}
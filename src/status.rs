@@ -0,0 +1,142 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+/// Fonts tried in order when a font from `assets/fonts` is missing
+const FALLBACK_FONTS: [&str; 6] = [
+    "/usr/share/fonts/truetype/dejavu/DejaVuSans-Bold.ttf",
+    "/usr/share/fonts/TTF/DejaVuSans-Bold.ttf",
+    "/usr/share/fonts/dejavu/DejaVuSans-Bold.ttf",
+    "/System/Library/Fonts/Supplemental/Arial Bold.ttf",
+    "/Library/Fonts/Arial Bold.ttf",
+    "C:\\Windows\\Fonts\\arialbd.ttf",
+];
+
+pub struct StatusPlugin;
+
+impl Plugin for StatusPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<StatusPanel>()
+            .add_system(status_ui)
+        ;
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StatusLevel {
+    Warning,
+    Error,
+}
+
+pub struct StatusEntry {
+    pub level: StatusLevel,
+    pub message: String,
+    /// Times the problem came back after going away
+    pub count: usize,
+    /// Resolved entries are hidden but kept to count recurrences
+    pub active: bool,
+}
+
+/// Problems the show can keep running with, listed in the "Status" window
+#[derive(Resource, Default)]
+pub struct StatusPanel {
+    pub entries: BTreeMap<&'static str, StatusEntry>,
+}
+
+impl StatusPanel {
+    /// Reports a problem under `key`. Systems report every frame the problem persists,
+    /// so it's only logged when first seen or when the message changes.
+    pub fn report(&mut self, key: &'static str, level: StatusLevel, message: impl Into<String>) {
+        let message = message.into();
+        let entry = self.entries.entry(key).or_insert_with(|| StatusEntry { level, message: String::new(), count: 0, active: false });
+        if !entry.active {
+            entry.active = true;
+            entry.count += 1;
+            entry.message.clear();
+        }
+        if entry.message != message {
+            match level {
+                StatusLevel::Warning => warn!("{}", message),
+                StatusLevel::Error => error!("{}", message),
+            }
+            entry.level = level;
+            entry.message = message;
+        }
+    }
+
+    pub fn warn(&mut self, key: &'static str, message: impl Into<String>) {
+        self.report(key, StatusLevel::Warning, message);
+    }
+
+    pub fn error(&mut self, key: &'static str, message: impl Into<String>) {
+        self.report(key, StatusLevel::Error, message);
+    }
+
+    /// Hides the entry once the problem went away
+    pub fn resolve(&mut self, key: &'static str) {
+        if let Some(entry) = self.entries.get_mut(key) {
+            entry.active = false;
+        }
+    }
+
+    pub fn active(&self) -> impl Iterator<Item = &StatusEntry> {
+        self.entries.values().filter(|e| e.active)
+    }
+}
+
+/// Loads a font from the assets folder, falling back to a system font if it's missing
+pub fn load_font(
+    path: &str,
+    assets: &AssetServer,
+    fonts: &mut Assets<Font>,
+    status: &mut StatusPanel,
+) -> Handle<Font> {
+    if assets.asset_io().is_file(Path::new(path)) {
+        return assets.load(path);
+    }
+
+    let file_name = Path::new(path).file_name().and_then(|n| n.to_str()).unwrap_or(path);
+    let fallback = FALLBACK_FONTS.iter()
+        .find_map(|p| Some((p, Font::try_from_bytes(fs::read(p).ok()?).ok()?)));
+    match fallback {
+        Some((fallback_path, font)) => {
+            status.warn("font", format!("{} missing, using fallback font {}", file_name, fallback_path));
+            fonts.add(font)
+        }
+        None => {
+            status.error("font", format!("{} missing and no fallback font found, text won't render", file_name));
+            Handle::default()
+        }
+    }
+}
+
+fn status_ui(
+    mut contexts: EguiContexts,
+    mut status: ResMut<StatusPanel>,
+) {
+    egui::Window::new("Status").show(contexts.ctx_mut(), |ui| {
+        if status.active().next().is_none() {
+            ui.label("All good");
+            return;
+        }
+
+        for entry in status.active() {
+            let color = match entry.level {
+                StatusLevel::Warning => egui::Color32::YELLOW,
+                StatusLevel::Error => egui::Color32::RED,
+            };
+            match entry.count {
+                1 => ui.colored_label(color, &entry.message),
+                count => ui.colored_label(color, format!("{} (×{})", entry.message, count)),
+            };
+        }
+
+        ui.separator();
+        if ui.button("Clear").clicked() {
+            status.entries.clear();
+        }
+    });
+}
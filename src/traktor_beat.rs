@@ -3,6 +3,8 @@ use bevy::utils::tracing::event;
 use bevy_pyree::beat::{BeatCounter, BeatEvent};
 use bevy_rosc::{MultiAddressOscMethod, OscDispatcher, SingleAddressOscMethod};
 use rosc::OscType;
+use crate::beat_controls::BeatSource;
+use crate::osc::schema::{AddOscSchema, ArgSchema, ArgSpec};
 
 pub struct TraktorPlugin;
 
@@ -12,6 +14,7 @@ impl Plugin for TraktorPlugin {
             .add_startup_system(spawn_traktor)
             .add_system(traktor_beat_system)
             .insert_resource(TraktorBeat::default())
+            .add_osc_schema("/traktor/volume", ArgSchema::new(vec![ArgSpec::int("volume", 0..=128)]))
         ;
     }
 }
@@ -43,6 +46,7 @@ pub fn traktor_beat_system(
     mut event_writer: EventWriter<BeatEvent>,
     mut beat_counter: ResMut<BeatCounter>,
     mut traktor_beat: ResMut<TraktorBeat>,
+    source: Res<BeatSource>,
 ) {
     let maybe = query.get_single_mut();
     if maybe.is_err() { return; }
@@ -60,7 +64,9 @@ pub fn traktor_beat_system(
         }
         if traktor_beat.count >= 24 {
             traktor_beat.count = 0;
-            event_writer.send(BeatEvent { count: beat_counter.count, bpm: None });
+            if *source == BeatSource::Traktor {
+                event_writer.send(BeatEvent { count: beat_counter.count, bpm: None });
+            }
         }
     }
 }